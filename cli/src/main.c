@@ -22,7 +22,7 @@ int main(void)
   printf("length: %d\n", (int)length);
   char* output = alloca(length+1);
   memset(output,0,length+1);
-  assert(khash_do(&ctx, string, strlen(string), output,length) == KHASH_SUCCESS);
+  assert(khash_do(&ctx, string, strlen(string), output, length+1) == KHASH_SUCCESS);
   printf("output: %s\n", output);
   return 0;
 }
@@ -32,15 +32,19 @@ int main(void)
 
 void k_do(const khash_ctx* ctx, const char* input)
 {
-  khash_ctx clone;
-  KTRY(khash_clone_context(ctx, &clone), "khash: ctxclone failed");
+  char* output;
+  KTRY(khash_hash(ctx, input, strlen(input), &output, NULL), "khash: hashstring failed");
+  printf("%s\n", output);
+  KTRY(khash_free_string(output), "khash: stringfree failed");
+}
 
+void k_do_path(const khash_ctx* ctx, const char* path)
+{
   size_t length;
-  KTRY(khash_length(&clone, input, strlen(input), &length), "khash: hashlength failed");
+  KTRY(khash_context_max_length(ctx, 0, &length), "khash: maxlength failed");
 
-  char* output =alloca(length+1);
-  KTRY(khash_do(&clone, input,strlen(input), output, length), "khash: hashstring failed");
-  output[length] = 0; //ensure no overflow.
+  char* output = alloca(length+1);
+  KTRY(khash_do_path(ctx, path, output, length+1, NULL, NULL), "khash: hashfile failed");
   printf("%s\n", output);
 }
 
@@ -65,17 +69,20 @@ static int _main(int argc, char** argv, khash_ctx ctx)
       if (strcmp(*argv, "--help") == 0)
 	{
 	  printf("kana-hash cli\n");
-	  printf("Usage: khash [--algo ALGO] [--salt SALT-TYPE [<salt>]] [--] <input strings...>\n");
+	  printf("Usage: khash [--algo ALGO] [--salt SALT-TYPE [<salt>]] [--file <path>] [--] <input strings...>\n");
 	  printf("  --algo: Specify the algorithm. (default sha256 truncated)\n");
 	  printf("    ALGO: 3: crc32.\n");
 	  printf("    ALGO: 6: crc64.\n");
 	  printf("    ALGO: s: sha256.\n");
 	  printf("    ALGO: t: truncated (64bit) sha256.\n");
+	  printf("    ALGO: b: blake3.\n");
+	  printf("    ALGO: B: truncated (64bit) blake3.\n");
 	  printf("  --salt: Specify the salt.\n");
 	  printf("    SALT_TYPE: D: default embedded.\n");
 	  printf("             : N: no salt.\n");
 	  printf("             : R: random salt.\n");
 	  printf("             : S <salt>: specific salt.\n");
+	  printf("  --file: Hash the contents of the file at <path> instead of a string.\n");
 	  printf("  --: Stop reading args here.\n");
 	  return 1;
 	}
@@ -101,6 +108,12 @@ static int _main(int argc, char** argv, khash_ctx ctx)
 		case 't':
 		  ctx.algo = KHASH_ALGO_SHA256_TRUNCATED;
 		  break;
+		case 'b':
+		  ctx.algo = KHASH_ALGO_BLAKE3;
+		  break;
+		case 'B':
+		  ctx.algo = KHASH_ALGO_BLAKE3_TRUNCATED;
+		  break;
 		default:
 		  fprintf(stderr, "ALGO: unknow algorithm key `%c'\n", *argv[1]);
 		  return 1;
@@ -108,6 +121,16 @@ static int _main(int argc, char** argv, khash_ctx ctx)
 	    }
 	  argv++;
 	}
+      else if (strcmp(*argv, "--file")==0)
+	{
+	  if (!argv[1])
+	    {
+	      fprintf(stderr, "--file expects a path.\n");
+	      return 1;
+	    }
+	  k_do_path(&ctx, argv[1]);
+	  argv++;
+	}
       else if (strcmp(*argv, "--salt")==0)
 	{
 	  if (argv[1])
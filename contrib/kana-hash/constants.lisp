@@ -6,6 +6,16 @@
                (KHASH_ERROR_FORMAT          . 2)
                (KHASH_ERROR_LENGTH          . 3)
                (KHASH_ERROR_RNG             . 4)
+               (KHASH_ERROR_INVALID_KANA    . 5)
+               (KHASH_ERROR_TRUNCATED       . 6)
+               (KHASH_ERROR_PARAMETERS      . 7)
+               (KHASH_ERROR_BUFFER_TOO_SMALL . 8)
+               (KHASH_ERROR_NULL_POINTER    . 9)
+               (KHASH_ERROR_EMPTY_INPUT     . 10)
+               (KHASH_ERROR_UNKNOWN_ALGORITHM . 11)
+               (KHASH_ERROR_UNKNOWN_SALT_TYPE . 12)
+               (KHASH_ERROR_SALT_TOO_LARGE  . 13)
+               (KHASH_ERROR_EMPTY_SALT      . 14)
                (KHASH_ERROR_UNKNOWN         . -1))
              collect `(defconstant ,name ,code)
              collect `(export (quote ,name))))
@@ -19,6 +29,23 @@
                (+algo-crc64+            . 2)
                (+algo-sha256+           . 3)
                (+algo-sha256-truncated+ . 4)
+               (+algo-hmac-sha256+      . 5)
+               (+algo-hmac-sha256-truncated+ . 6)
+               (+algo-blake3+           . 7)
+               (+algo-blake3-truncated+ . 8)
+               (+algo-sha512+           . 9)
+               (+algo-sha512-256+       . 10)
+               (+algo-sha3-256+         . 11)
+               (+algo-xxh3-64+          . 12)
+               (+algo-xxh3-128+         . 13)
+               (+algo-siphash13+        . 14)
+               (+algo-crc32c+           . 15)
+               (+algo-crc64-iso+        . 16)
+               (+algo-crc64-xz+         . 2)
+               (+algo-argon2id+         . 17)
+               (+algo-scrypt+           . 18)
+               (+algo-pbkdf2+           . 19)
+               (+algo-shake256+         . 20)
                
                (+salt-none+     . 0)
                (+salt-default+  . 1)
@@ -48,6 +48,23 @@
   (string :pointer)
   (strlen :long))
 
+(defcfun "khash_context_max_length" :int
+  (context :pointer)
+  (input-length :long)
+  (digest-length :pointer))
+
+(defcfun "khash_do_path" :int
+  (context :pointer)
+  (path :string)
+  (string :pointer)
+  (strlen :long)
+  (read :pointer)
+  (length :pointer))
+
+(defcfun "khash_last_error_message" :int
+  (buffer :pointer)
+  (size :long))
+
 (defmacro with-khash-context (ctx &body body)
   `(with-foreign-object (,ctx '(:struct khash-ctx))
      ,@body))
@@ -107,10 +124,32 @@
                                     :long (1+ length)
                                     :pointer)
           (values 
-           (khash-do context data len string length)
+           (khash-do context data len string (1+ length))
            (foreign-string-to-lisp string)))))))
 
+(defun ffi-khash-do-path (ctx path)
+  (initialise-khash-context context ctx
+    (with-foreign-object (max-length :long)
+      (khash-context-max-length context 0 max-length)
+      (let ((length (mem-ref max-length :long)))
+        (with-foreign-pointer (string (1+ length))
+          (foreign-funcall "memset" :pointer string
+                                    :int 0
+                                    :long (1+ length)
+                                    :pointer)
+          (values
+           (khash-do-path context path string (1+ length) (null-pointer) (null-pointer))
+           (foreign-string-to-lisp string)))))))
+
+(defun ffi-khash-last-error-message ()
+  (let ((length (khash-last-error-message (null-pointer) 0)))
+    (with-foreign-pointer (buffer (1+ length))
+      (khash-last-error-message buffer (1+ length))
+      (foreign-string-to-lisp buffer))))
+
 (export '(ffi-khash-do
+          ffi-khash-last-error-message
+          ffi-khash-do-path
           ffi-khash-length
           ffi-khash-clone-context
           ffi-khash-free-context
@@ -8,12 +8,26 @@
 
 (defun handle-kana-hash-error (error-code)
   (error 'kana-hash-error :msg
-         (cond
-           ((= error-code KHASH_ERROR_IO) "IO Error")
-           ((= error-code KHASH_ERROR_FORMAT) "Format Error")
-           ((= error-code KHASH_ERROR_LENGTH) "Length Error")
-           ((= error-code KHASH_ERROR_RNG) "RNG Error")
-           ((= error-code KHASH_ERROR_UNKNOWN) "Unknown Error"))))
+         (let ((message (ffi-khash-last-error-message)))
+           (if (plusp (length message))
+               message
+               (kana-hash-error-name error-code)))))
+
+(defun kana-hash-error-name (error-code)
+  (cond
+    ((= error-code KHASH_ERROR_IO) "IO Error")
+    ((= error-code KHASH_ERROR_FORMAT) "Format Error")
+    ((= error-code KHASH_ERROR_LENGTH) "Length Error")
+    ((= error-code KHASH_ERROR_RNG) "RNG Error")
+    ((= error-code KHASH_ERROR_PARAMETERS) "Parameters Error")
+    ((= error-code KHASH_ERROR_BUFFER_TOO_SMALL) "Buffer Too Small")
+    ((= error-code KHASH_ERROR_NULL_POINTER) "Null Pointer")
+    ((= error-code KHASH_ERROR_EMPTY_INPUT) "Empty Input")
+    ((= error-code KHASH_ERROR_UNKNOWN_ALGORITHM) "Unknown Algorithm")
+    ((= error-code KHASH_ERROR_UNKNOWN_SALT_TYPE) "Unknown Salt Type")
+    ((= error-code KHASH_ERROR_SALT_TOO_LARGE) "Salt Too Large")
+    ((= error-code KHASH_ERROR_EMPTY_SALT) "Empty Salt")
+    ((= error-code KHASH_ERROR_UNKNOWN) "Unknown Error")))
 
 (defmacro with-success (thing function &body body)
   `(multiple-value-bind (return-code ,thing) ,function
@@ -44,4 +58,14 @@
         (setf ctx nil)
         kana-hash))))
 
-(export 'make-hash)
+(defun make-file-hash (path &key (algo +algo-default+) (salt-type +salt-default+) salt
+                       &aux (salt (or salt nil)))
+  (when salt
+    (setf salt-type +salt-specific+))
+  (unless salt
+    (setf salt (format nil "~a" salt)))
+  (with-context (ctx algo salt-type salt)
+    (with-success kana-hash (ffi-khash-do-path ctx (namestring path))
+      kana-hash)))
+
+(export '(make-hash make-file-hash))
@@ -7,6 +7,8 @@ extern "C" {
 #endif
 
 #include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
 
 #ifdef __GNUC__
 #define _deprecated(x) __attribute__((deprecated))
@@ -14,11 +16,31 @@ extern "C" {
 #define _deprecated(x)
 #endif
 
+  /// The version of the C ABI described by this header. Compare with `khash_abi_version()` at load time; it is bumped whenever an exported function or struct changes incompatibly.
+#define KHASH_ABI_VERSION ((uint32_t)1)
+
 #define KHASH_ALGO_DEFAULT ((uint8_t)0)
 #define KHASH_ALGO_CRC32 ((uint8_t)1)
 #define KHASH_ALGO_CRC64 ((uint8_t)2)
 #define KHASH_ALGO_SHA256 ((uint8_t)3)
 #define KHASH_ALGO_SHA256_TRUNCATED ((uint8_t)4) /* SHA256 truncated to 64 bits */
+#define KHASH_ALGO_HMAC_SHA256 ((uint8_t)5) /* HMAC-SHA256 keyed with the salt */
+#define KHASH_ALGO_HMAC_SHA256_TRUNCATED ((uint8_t)6) /* HMAC-SHA256 keyed with the salt, truncated to 64 bits */
+#define KHASH_ALGO_BLAKE3 ((uint8_t)7)
+#define KHASH_ALGO_BLAKE3_TRUNCATED ((uint8_t)8) /* BLAKE3 truncated to 64 bits */
+#define KHASH_ALGO_SHA512 ((uint8_t)9)
+#define KHASH_ALGO_SHA512_256 ((uint8_t)10) /* SHA-512/256 */
+#define KHASH_ALGO_SHA3_256 ((uint8_t)11)
+#define KHASH_ALGO_XXH3_64 ((uint8_t)12)
+#define KHASH_ALGO_XXH3_128 ((uint8_t)13)
+#define KHASH_ALGO_SIPHASH13 ((uint8_t)14) /* SipHash-1-3 keyed with the salt */
+#define KHASH_ALGO_CRC32C ((uint8_t)15) /* CRC-32C (Castagnoli), hardware accelerated where available */
+#define KHASH_ALGO_CRC64_ISO ((uint8_t)16) /* CRC-64/GO-ISO */
+#define KHASH_ALGO_CRC64_XZ KHASH_ALGO_CRC64 /* `KHASH_ALGO_CRC64` is CRC-64/XZ */
+#define KHASH_ALGO_ARGON2ID ((uint8_t)17) /* Password hashing, see `khash_new_context_with_cost` */
+#define KHASH_ALGO_SCRYPT ((uint8_t)18) /* Password hashing, see `khash_new_context_with_cost` */
+#define KHASH_ALGO_PBKDF2 ((uint8_t)19) /* PBKDF2-HMAC-SHA256 password hashing, see `khash_new_context_with_cost` */
+#define KHASH_ALGO_SHAKE256 ((uint8_t)20) /* Extendable output, see `KHASH_LENGTH_FLAGS` */
   
   /// No salt
 #define KHASH_SALT_TYPE_NONE ((uint8_t)0)
@@ -29,6 +51,9 @@ extern "C" {
   /// A randomly generated salt.
 #define KHASH_SALT_TYPE_RANDOM ((uint8_t)3)
 
+  /// The maximum size in bytes of a `KHASH_SALT_TYPE_SPECIFIC` salt.
+#define KHASH_MAX_SALT_SIZE ((size_t)1024)
+
   /// A valid salt for khash functions. Instantiated with `khash_new_salt`.
   typedef struct {
     uint8_t salt_type;
@@ -36,6 +61,26 @@ extern "C" {
     uint8_t* body;
   } khash_salt;
 
+  /// The upper 32 bits of `khash_ctx.flags` hold the cost parameters for the password-hashing algorithms. (`0` means the algorithm's default.)
+  /// Bits 32-52 are the iteration count, bits 53-58 are the base 2 logarithm of the memory cost in KiB, and bits 59-63 are the parallelism.
+#define KHASH_COST_FLAGS(iterations, log2_memory_kib, parallelism) \
+  ((((uint64_t)(iterations) & 0x1fffff) << 32) | (((uint64_t)(log2_memory_kib) & 0x3f) << 53) | (((uint64_t)(parallelism) & 0x1f) << 59))
+
+  /// Bits 16-31 of `khash_ctx.flags` hold the requested digest length in bytes, or `0` for the algorithm's default.
+  /// `KHASH_ALGO_SHAKE256` and the BLAKE3 algorithms produce exactly this many bytes, the others are truncated to it (and fail with `KHASH_ERROR_PARAMETERS` if it is longer than their digest.)
+#define KHASH_LENGTH_FLAGS(length) (((uint64_t)(length) & 0xffff) << 16)
+
+  /// Bits 0-15 of `khash_ctx.flags` are output options, which can be combined.
+  /// Unknown flags, or both `KHASH_FLAG_HIRAGANA` and `KHASH_FLAG_KATAKANA`, are rejected with `KHASH_ERROR_PARAMETERS`.
+  /// Write only hiragana. Katakana are written as the equivalent hiragana.
+#define KHASH_FLAG_HIRAGANA ((uint64_t)1 << 0)
+  /// Write only katakana. Hiragana are written as the equivalent katakana.
+#define KHASH_FLAG_KATAKANA ((uint64_t)1 << 1)
+  /// Write a `-` between each group of 2 kana (each 2 bytes of digest.)
+#define KHASH_FLAG_SEPARATORS ((uint64_t)1 << 2)
+  /// Do not write a NUL terminator after the hash in caller-provided buffers. (Strings allocated by the library are always terminated.)
+#define KHASH_FLAG_NO_NUL_TERMINATOR ((uint64_t)1 << 3)
+
   /// A valid context for khash functinos. Instantiated with `khash_new_context`.
   typedef struct {
     uint8_t algo;
@@ -43,6 +88,25 @@ extern "C" {
     khash_salt salt;
   } khash_ctx;
 
+  /// A read callback for `khash_do_reader`. Should read no more than `size` bytes into `buffer`, returning the number of bytes read, `0` at the end of the input, or a negative value on error.
+  typedef ptrdiff_t (*khash_read_cb)(void* userdata, void* buffer, size_t size);
+
+  /// An opaque streaming hash state. Instantiated with `khash_stream_new`.
+  typedef struct khash_stream khash_stream;
+
+  /// Information about an algorithm compiled into the library. Filled by `khash_algorithm_info`.
+  typedef struct {
+    uint8_t id; /* One of the `KHASH_ALGO_*` constants */
+    const char* name; /* Static NUL-terminated name */
+    size_t digest_size; /* Digest size in bytes when no length is requested */
+    bool cryptographic; /* A cryptographic hash, rather than a checksum */
+    bool extendable; /* Can produce digests of any requested length */
+  } khash_algo_info;
+
+  /// An opaque heap-allocated context. Instantiated with `khash_context_create`.
+  /// Unlike `khash_ctx`, its layout is not part of the ABI.
+  typedef struct khash_context khash_context;
+
   /// Returned by all functions that succeed.
 #define KHASH_SUCCESS ((int32_t)0)
 
@@ -54,27 +118,152 @@ extern "C" {
 #define KHASH_ERROR_LENGTH ((int32_t)3)
   /// Random number generation failed
 #define KHASH_ERROR_RNG ((int32_t)4)
+  /// Decoding found an invalid kana
+#define KHASH_ERROR_INVALID_KANA ((int32_t)5)
+  /// Decoding input was truncated
+#define KHASH_ERROR_TRUNCATED ((int32_t)6)
+  /// Invalid algorithm parameters (e.g. cost, or salt too short)
+#define KHASH_ERROR_PARAMETERS ((int32_t)7)
+  /// An output buffer was too small. As much of the output as fits without splitting a kana was written, followed by a NUL terminator (unless `KHASH_FLAG_NO_NUL_TERMINATOR` is set) if the buffer was not empty.
+#define KHASH_ERROR_BUFFER_TOO_SMALL ((int32_t)8)
+  /// A required pointer argument (e.g. the input `data`) was `NULL`
+#define KHASH_ERROR_NULL_POINTER ((int32_t)9)
+  /// The input `data` to hash was empty (`size` was `0`)
+#define KHASH_ERROR_EMPTY_INPUT ((int32_t)10)
+  /// An `algo` (or `khash_ctx.algo`) was not one of the `KHASH_ALGO_*` constants, or the algorithm was not compiled into the library
+#define KHASH_ERROR_UNKNOWN_ALGORITHM ((int32_t)11)
+  /// A `salt_type` (or `khash_salt.salt_type`) was not one of the `KHASH_SALT_TYPE_*` constants
+#define KHASH_ERROR_UNKNOWN_SALT_TYPE ((int32_t)12)
+  /// A `KHASH_SALT_TYPE_SPECIFIC` salt was larger than `KHASH_MAX_SALT_SIZE`
+#define KHASH_ERROR_SALT_TOO_LARGE ((int32_t)13)
+  /// A `KHASH_SALT_TYPE_SPECIFIC` salt was empty
+#define KHASH_ERROR_EMPTY_SALT ((int32_t)14)
   /// Unknown error
 #define KHASH_ERROR_UNKNOWN ((int32_t)-1)
 
-  /// Find the maximum possible digest output size for the given algorithm and input length, and set that to `digest_length`. If the algorithm's output is not dynamically sized `input_length` does not need to be provided.
+  /// Write the message of the last error returned by a khash function on this thread into `buffer`, truncated to fit in `size` bytes including a NUL terminator.
+  /// Returns the full length of the message in bytes not including the NUL terminator, or `0` if there has been no error. Successful calls do not clear the message.
+  extern int32_t khash_last_error_message(char* buffer, size_t size);
+  /// Get a static NUL-terminated description of a `KHASH_ERROR_*` code.
+  extern const char* khash_strerror(int32_t code);
+
+  /// Get the version of the C ABI exported by the library. Bindings should check this is `KHASH_ABI_VERSION`.
+  extern uint32_t khash_abi_version(void);
+  /// Get the version of the library as a static NUL-terminated string.
+  extern const char* khash_version(void);
+
+  /// Get the number of algorithms compiled into the library.
+  extern size_t khash_algorithm_count(void);
+  /// Fill `info` with information about the algorithm at `index`, which must be less than `khash_algorithm_count()` (otherwise `KHASH_ERROR_PARAMETERS` is returned.)
+  extern int32_t khash_algorithm_info(size_t index, khash_algo_info* info);
+  /// Get the `KHASH_ALGO_*` value of the algorithm at `index`, which should be less than `khash_algorithm_count()` (otherwise `KHASH_ALGO_DEFAULT` is returned.)
+  extern uint8_t khash_algorithm_id(size_t index);
+  /// Get the static NUL-terminated name of the algorithm at `index`, or `NULL` if `index` is out of range.
+  extern const char* khash_algorithm_name(size_t index);
+  /// Get the digest size in bytes of the algorithm at `index` when no length is requested, or `0` if `index` is out of range.
+  extern size_t khash_algorithm_digest_size(size_t index);
+  /// Check if the algorithm at `index` is a cryptographic hash, rather than a checksum. `false` if `index` is out of range.
+  extern bool khash_algorithm_cryptographic(size_t index);
+  /// Check if the algorithm at `index` can produce digests of any requested length. `false` if `index` is out of range.
+  extern bool khash_algorithm_extendable(size_t index);
+  /// Check if `algo` (one of the `KHASH_ALGO_*` constants) is compiled into the library. Other functions return `KHASH_ERROR_UNKNOWN_ALGORITHM` for unsupported algorithms.
+  extern bool khash_algorithm_supported(uint8_t algo);
+
+  /// Find the maximum possible output size in bytes (not including a NUL terminator) for the given algorithm and input length, and set that to `digest_length`. If the algorithm's output is not dynamically sized `input_length` does not need to be provided.
+  /// This is a bound for any requested digest length and output flags. For `KHASH_ALGO_SHAKE256` and BLAKE3, which can produce up to 65535 bytes of digest, it is large; `khash_context_max_length` gives the exact size for a context.
   extern int32_t khash_max_length(uint8_t algo, size_t input_length, size_t* digest_length);
 
+  /// Find the maximum possible output size for `context`, taking its requested digest length and output flags into account, and set that to `digest_length`.
+  /// This function does not free `context` after it has been called.
+  extern int32_t khash_context_max_length(const khash_ctx* context, size_t input_length, size_t* digest_length);
+
   /// Create a new context with the specified algorithm (one of the `KHASH_ALGO_*` macro constants), salt type (one of the `KHASH_SALT_TYPE_*` constants), optional salt `data` and salt length `size`, and output pointer `output`.
-  /// `data` may be `NULL` if the corresponding `salt_type` does not require an input.
+  /// `data` may be `NULL` if the corresponding `salt_type` does not require an input. A `KHASH_SALT_TYPE_SPECIFIC` salt must be between 1 and `KHASH_MAX_SALT_SIZE` bytes.
   extern int32_t khash_new_context(uint8_t algo, uint8_t salt_type, const void* data, size_t size, khash_ctx* output);
+  /// Like `khash_new_context`, but with cost parameters for the password-hashing algorithms (`KHASH_ALGO_ARGON2ID`, `KHASH_ALGO_SCRYPT`, `KHASH_ALGO_PBKDF2`.)
+  /// A value of `0` for any cost uses the algorithm's default. `memory_kib` must be a power of 2, `iterations` must be less than 2^21 and `parallelism` less than 32, otherwise `KHASH_ERROR_PARAMETERS` is returned.
+  extern int32_t khash_new_context_with_cost(uint8_t algo, uint8_t salt_type, const void* data, size_t size, uint32_t iterations, uint32_t memory_kib, uint32_t parallelism, khash_ctx* output);
   /// Free a `khash_ctx` allocated with `khash_new_context`.
   extern int32_t khash_free_context(khash_ctx* ctx);
   /// Clone a `khash_ctx` allocated with `khash_new_context`. The clone is a newly allocated instance.
   extern int32_t khash_clone_context(const khash_ctx* src, khash_ctx* dst);
   
+  /// The functions taking `data` and `size` read the input in place without copying it. `data` must not be `NULL` (`KHASH_ERROR_NULL_POINTER`) and `size` must not be `0` (`KHASH_ERROR_EMPTY_INPUT`.)
+
   /// Compute the length of hash required for the specified input.
   /// This function does not free `context` after it has been called.
   extern int32_t khash_length(const khash_ctx* context, const void* data, size_t size, size_t* length);
-  /// Compute the hash and store it in `string`. Will write no more than `strlen` bytes into `string`.
+  /// Compute the hash and store it in `string`. Will write no more than `strlen` bytes into `string`, including a NUL terminator unless `KHASH_FLAG_NO_NUL_TERMINATOR` is set, so `strlen` must leave room for it.
+  /// If the hash does not fit, it is truncated on a kana boundary and `KHASH_ERROR_BUFFER_TOO_SMALL` is returned. (See `khash_length` for the size required.)
   /// This function takes ownership of and frees `context` after it has been called.
   extern int32_t khash_do(khash_ctx* context, const void* data, size_t size, char* string, size_t strlen);
 
+  /// Compute the hash of `size` bytes from `data` into a newly allocated NUL-terminated string, and store it in `string`. If `length` is not `NULL`, the length of the hash (not including the NUL terminator) is stored there.
+  /// The string must be freed with `khash_free_string`.
+  /// This function does not free `context` after it has been called.
+  extern int32_t khash_hash(const khash_ctx* context, const void* data, size_t size, char** string, size_t* length);
+  /// Free a string allocated by `khash_hash`. `string` may be `NULL`.
+  extern int32_t khash_free_string(char* string);
+
+  /// Compute the hash of `data` and compare it with the NUL-terminated kana string `expected` in constant time, setting `valid` to the result.
+  /// This function does not free `context` after it has been called.
+  extern int32_t khash_verify(const khash_ctx* context, const void* data, size_t size, const char* expected, bool* valid);
+#if defined(__unix__) || defined(__APPLE__)
+  /// Compute the hash of everything read from the file descriptor `fd` and compare it with the NUL-terminated kana string `expected` in constant time, setting `valid` to the result.
+  /// If `read` is not `NULL`, the number of bytes read is stored there. `fd` is not closed.
+  /// This function does not free `context` after it has been called.
+  /// Only available on unix platforms.
+  extern int32_t khash_verify_stream(const khash_ctx* context, int fd, const char* expected, size_t* read, bool* valid);
+#endif
+
+  /// Compute the hash of everything read from the file descriptor `fd` and store it in `string`. Will write no more than `strlen` bytes into `string`, including a NUL terminator unless `KHASH_FLAG_NO_NUL_TERMINATOR` is set, so `strlen` must leave room for it.
+  /// If `read` is not `NULL`, the number of bytes read is stored there. If `length` is not `NULL`, the full length of the hash (the size required, not including a NUL terminator) is stored there. `fd` is not closed.
+  /// If the hash does not fit, it is truncated on a kana boundary and `KHASH_ERROR_BUFFER_TOO_SMALL` is returned. `string` may be `NULL` to only find the length.
+  /// This function does not free `context` after it has been called.
+  /// Only available on unix platforms.
+#if defined(__unix__) || defined(__APPLE__)
+  extern int32_t khash_do_fd(const khash_ctx* context, int fd, char* string, size_t strlen, size_t* read, size_t* length);
+#endif
+  /// Like `khash_do_fd`, but reads the contents of the file at the NUL-terminated `path`. Failing to open the file returns `KHASH_ERROR_IO`.
+  extern int32_t khash_do_path(const khash_ctx* context, const char* path, char* string, size_t strlen, size_t* read, size_t* length);
+  /// Like `khash_do_fd`, but reads by calling `callback` with `userdata` until it returns `0`. A negative return from `callback` returns `KHASH_ERROR_IO`.
+  extern int32_t khash_do_reader(const khash_ctx* context, khash_read_cb callback, void* userdata, char* string, size_t strlen, size_t* read, size_t* length);
+
+  /// Create a new streaming hash state for `context` and store it in `stream`.
+  /// Memory use is constant regardless of the input size, except for Argon2id which buffers its input.
+  /// This function does not free `context` after it has been called.
+  extern int32_t khash_stream_new(const khash_ctx* context, khash_stream** stream);
+  /// Feed `size` bytes from `data` into `stream`. `data` may be `NULL` if `size` is `0`, otherwise `KHASH_ERROR_NULL_POINTER` is returned.
+  extern int32_t khash_stream_update(khash_stream* stream, const void* data, size_t size);
+  /// Finish `stream` and store the hash in `string`, as `khash_do_fd` does. If `length` is not `NULL`, the full length of the hash is stored there.
+  /// `stream` cannot be updated or finished again after this (`KHASH_ERROR_PARAMETERS` is returned), but must still be freed with `khash_stream_free`.
+  extern int32_t khash_stream_finish(khash_stream* stream, char* string, size_t strlen, size_t* length);
+  /// Free a streaming hash state allocated with `khash_stream_new`. `stream` may be `NULL`.
+  extern int32_t khash_stream_free(khash_stream* stream);
+
+  /// Create a new opaque context with the default algorithm and salt, and store it in `context`. It must be freed with `khash_context_destroy`.
+  extern int32_t khash_context_create(khash_context** context);
+  /// Clone `src` into a newly allocated opaque context stored in `dst`.
+  extern int32_t khash_context_clone(const khash_context* src, khash_context** dst);
+  /// Free an opaque context allocated with `khash_context_create` or `khash_context_clone`. `context` may be `NULL`.
+  extern int32_t khash_context_destroy(khash_context* context);
+  /// Set the algorithm of `context` to one of the `KHASH_ALGO_*` constants.
+  extern int32_t khash_context_set_algorithm(khash_context* context, uint8_t algo);
+  /// Set the salt of `context`, as `khash_new_context` does.
+  extern int32_t khash_context_set_salt(khash_context* context, uint8_t salt_type, const void* data, size_t size);
+  /// Set the cost parameters of `context` for the password-hashing algorithms. A value of `0` for any cost uses the algorithm's default.
+  /// `memory_kib` must be a power of 2, `iterations` must be less than 2^21 and `parallelism` less than 32, otherwise `KHASH_ERROR_PARAMETERS` is returned and the cost is unchanged.
+  extern int32_t khash_context_set_cost(khash_context* context, uint32_t iterations, uint32_t memory_kib, uint32_t parallelism);
+  /// Set the requested digest length of `context` in bytes, or `0` for the algorithm's default. (See `KHASH_LENGTH_FLAGS`.)
+  /// Lengths longer than 65535 bytes are rejected with `KHASH_ERROR_PARAMETERS`.
+  extern int32_t khash_context_set_length(khash_context* context, size_t length);
+  /// Set the output flags of `context` to a combination of the `KHASH_FLAG_*` constants.
+  extern int32_t khash_context_set_flags(khash_context* context, uint16_t flags);
+  /// Like `khash_hash`, but with an opaque context.
+  extern int32_t khash_context_hash(const khash_context* context, const void* data, size_t size, char** string, size_t* length);
+  /// Like `khash_stream_new`, but with an opaque context.
+  extern int32_t khash_context_stream_new(const khash_context* context, khash_stream** stream);
+
   /// Create a new salt. `salt_type` is expected to be one of the above defined `KHASH_SALT_TYPE_*` macros.
   /// Depending on the type, `data` may be `NULL`.
   extern int32_t khash_new_salt(uint8_t salt_type, const void* data, size_t size, khash_salt* output) _deprecated("Use `khash_new_context` instead."); 
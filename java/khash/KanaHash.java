@@ -0,0 +1,200 @@
+package khash;
+
+import java.io.IOException;
+import java.io.InputStream;
+import java.nio.ByteBuffer;
+import java.nio.charset.StandardCharsets;
+import java.util.Objects;
+
+/**
+ * A kana-hash context, backed by a native context that is released by {@link #close()}.
+ *
+ * Instances are not thread safe, use {@link #copy()} to hash on several threads.
+ * Errors are thrown as {@link KanaHashException}.
+ */
+public final class KanaHash implements AutoCloseable {
+    static {
+	System.loadLibrary("khash");
+    }
+
+    /* The algorithms, the same values as the `KHASH_ALGO_*` constants */
+    public static final int ALGO_DEFAULT = 0;
+    public static final int ALGO_CRC32 = 1;
+    public static final int ALGO_CRC64 = 2;
+    public static final int ALGO_SHA256 = 3;
+    public static final int ALGO_SHA256_TRUNCATED = 4;
+    public static final int ALGO_HMAC_SHA256 = 5;
+    public static final int ALGO_HMAC_SHA256_TRUNCATED = 6;
+    public static final int ALGO_BLAKE3 = 7;
+    public static final int ALGO_BLAKE3_TRUNCATED = 8;
+    public static final int ALGO_SHA512 = 9;
+    public static final int ALGO_SHA512_256 = 10;
+    public static final int ALGO_SHA3_256 = 11;
+    public static final int ALGO_XXH3_64 = 12;
+    public static final int ALGO_XXH3_128 = 13;
+    public static final int ALGO_SIPHASH13 = 14;
+    public static final int ALGO_CRC32C = 15;
+    public static final int ALGO_CRC64_ISO = 16;
+    public static final int ALGO_ARGON2ID = 17;
+    public static final int ALGO_SCRYPT = 18;
+    public static final int ALGO_PBKDF2 = 19;
+    public static final int ALGO_SHAKE256 = 20;
+
+    /* Output flags for `setFlags`, the same values as the `KHASH_FLAG_*` constants */
+    public static final int FLAG_HIRAGANA = 1;
+    public static final int FLAG_KATAKANA = 2;
+    public static final int FLAG_SEPARATORS = 4;
+
+    private static final int SALT_TYPE_NONE = 0;
+    private static final int SALT_TYPE_DEFAULT = 1;
+    private static final int SALT_TYPE_SPECIFIC = 2;
+    private static final int SALT_TYPE_RANDOM = 3;
+
+    private long handle;
+
+    private KanaHash(long handle) {
+	this.handle = handle;
+    }
+
+    /** A context with the default algorithm and salt. */
+    public KanaHash() {
+	this(ALGO_DEFAULT);
+    }
+
+    /** A context with `algorithm` and the default salt. */
+    public KanaHash(int algorithm) {
+	this(create(algorithm, SALT_TYPE_DEFAULT, null));
+    }
+
+    /** A context with `algorithm` and a specific salt, which cannot be empty. */
+    public KanaHash(int algorithm, byte[] salt) {
+	this(create(algorithm, SALT_TYPE_SPECIFIC, Objects.requireNonNull(salt, "salt")));
+    }
+
+    /** A context with `algorithm` and the UTF-8 bytes of `salt` as the salt. */
+    public KanaHash(int algorithm, String salt) {
+	this(algorithm, Objects.requireNonNull(salt, "salt").getBytes(StandardCharsets.UTF_8));
+    }
+
+    /** A context with `algorithm` and no salt. */
+    public static KanaHash unsalted(int algorithm) {
+	return new KanaHash(create(algorithm, SALT_TYPE_NONE, null));
+    }
+
+    /** A context with `algorithm` and a cryptographically secure random salt. */
+    public static KanaHash withRandomSalt(int algorithm) {
+	return new KanaHash(create(algorithm, SALT_TYPE_RANDOM, null));
+    }
+
+    /** A new context with the same settings, which must also be closed. */
+    public KanaHash copy() {
+	return new KanaHash(copy(handle()));
+    }
+
+    /** Set the cost parameters of the password-hashing algorithms, `0` uses the algorithm's default. */
+    public KanaHash setCost(int iterations, int memoryKib, int parallelism) {
+	setCost(handle(), iterations, memoryKib, parallelism);
+	return this;
+    }
+
+    /** Request a digest length in bytes, `0` for the algorithm's own. */
+    public KanaHash setLength(int length) {
+	setLength(handle(), length);
+	return this;
+    }
+
+    /** Set the output flags, a combination of the `FLAG_*` constants. */
+    public KanaHash setFlags(int flags) {
+	setFlags(handle(), flags);
+	return this;
+    }
+
+    /** The size in bytes of the digest. */
+    public int digestSize() {
+	return digestSize(handle());
+    }
+
+    /** The length in bytes (as UTF-8) of the kana hashes. */
+    public int kanaLength() {
+	return kanaLength(handle());
+    }
+
+    /** Compute the kana hash of `data`. */
+    public String hash(byte[] data) {
+	return hash(data, 0, data.length);
+    }
+
+    /** Compute the kana hash of `length` bytes of `data` from `offset`. */
+    public String hash(byte[] data, int offset, int length) {
+	Objects.checkFromIndexSize(offset, length, data.length);
+	return hashBytes(handle(), data, offset, length);
+    }
+
+    /** Compute the kana hash of the UTF-8 bytes of `data`. */
+    public String hash(String data) {
+	return hash(data.getBytes(StandardCharsets.UTF_8));
+    }
+
+    /**
+     * Compute the kana hash of the remaining bytes of `buffer`, without changing its position.
+     *
+     * Direct buffers are hashed in place.
+     */
+    public String hash(ByteBuffer buffer) {
+	if (buffer.isDirect())
+	    return hashDirect(handle(), buffer, buffer.position(), buffer.remaining());
+	if (buffer.hasArray())
+	    return hash(buffer.array(), buffer.arrayOffset() + buffer.position(), buffer.remaining());
+	byte[] data = new byte[buffer.remaining()];
+	buffer.duplicate().get(data);
+	return hash(data);
+    }
+
+    /** Compute the kana hash of everything read from `stream`, which is not closed. Exceptions thrown by `stream` are propagated. */
+    public String hash(InputStream stream) throws IOException {
+	return hashStream(handle(), Objects.requireNonNull(stream, "stream"));
+    }
+
+    /** Check the kana hash of `data` against `expected` in constant time. */
+    public boolean verify(byte[] data, String expected) {
+	return verifyHash(hash(data), Objects.requireNonNull(expected, "expected"));
+    }
+
+    /** Check the kana hash of the remaining bytes of `buffer` against `expected` in constant time. */
+    public boolean verify(ByteBuffer buffer, String expected) {
+	return verifyHash(hash(buffer), Objects.requireNonNull(expected, "expected"));
+    }
+
+    /** Check the kana hash of everything read from `stream` against `expected` in constant time. */
+    public boolean verify(InputStream stream, String expected) throws IOException {
+	return verifyHash(hash(stream), Objects.requireNonNull(expected, "expected"));
+    }
+
+    /** Release the native context. Using the context afterwards throws `IllegalStateException`. */
+    @Override
+    public void close() {
+	if (handle != 0) {
+	    destroy(handle);
+	    handle = 0;
+	}
+    }
+
+    private long handle() {
+	if (handle == 0)
+	    throw new IllegalStateException("KanaHash is closed");
+	return handle;
+    }
+
+    private static native long create(int algorithm, int saltType, byte[] salt);
+    private static native long copy(long handle);
+    private static native void destroy(long handle);
+    private static native void setCost(long handle, int iterations, int memoryKib, int parallelism);
+    private static native void setLength(long handle, int length);
+    private static native void setFlags(long handle, int flags);
+    private static native int digestSize(long handle);
+    private static native int kanaLength(long handle);
+    private static native String hashBytes(long handle, byte[] data, int offset, int length);
+    private static native String hashDirect(long handle, ByteBuffer buffer, int position, int length);
+    private static native String hashStream(long handle, InputStream stream) throws IOException;
+    private static native boolean verifyHash(String hash, String expected);
+}
@@ -0,0 +1,10 @@
+package khash;
+
+/**
+ * An error from the kana-hash library.
+ */
+public class KanaHashException extends RuntimeException {
+    public KanaHashException(String message) {
+	super(message);
+    }
+}
@@ -1,62 +1,81 @@
 const ffi = require('ffi-napi');
-const struct = require('ref-struct-napi');
 const ref = require('ref-napi');
 
-const Salt = struct({
-    'type': 'char',
-    'size': 'int',
-    'body': 'pointer',
-});
-const PSalt = ref.refType(Salt);
+/// The version of the library's C ABI these bindings are written for.
+const ABI_VERSION = 1;
 
-const Context = struct({
-    'algo': 'char',
-    'flags': 'long',
-    'salt': Salt,
-});
-const PContext = ref.refType(Context);
-const PLong = ref.refType(ref.types.long);
+const PHandle = ref.refType('pointer');
+const PSize = ref.refType(ref.types.size_t);
 
 const lib = ffi.Library('libkhash', {
-    'khash_new_context': ['int', ['char', 'char', 'string', 'long', PContext]],
-    'khash_free_context': ['int', [PContext]],
-    'khash_clone_context': ['int', [PContext, PContext]],
-
-    'khash_length': ['int', [PContext, 'string', 'long', PLong]],
-    'khash_do': ['int', [PContext, 'string', 'long', 'string', 'long']],
-    'khash_max_length': ['int', ['char', 'long', PLong]],
+    'khash_abi_version': ['uint32', []],
+    'khash_version': ['string', []],
+    'khash_algorithm_count': ['size_t', []],
+    'khash_algorithm_id': ['uint8', ['size_t']],
+    'khash_algorithm_name': ['string', ['size_t']],
+    'khash_algorithm_digest_size': ['size_t', ['size_t']],
+    'khash_algorithm_cryptographic': ['bool', ['size_t']],
+    'khash_algorithm_extendable': ['bool', ['size_t']],
+    'khash_algorithm_supported': ['bool', ['uint8']],
+
+    'khash_context_create': ['int', [PHandle]],
+    'khash_context_clone': ['int', ['pointer', PHandle]],
+    'khash_context_destroy': ['int', ['pointer']],
+    'khash_context_set_algorithm': ['int', ['pointer', 'uint8']],
+    'khash_context_set_salt': ['int', ['pointer', 'uint8', 'pointer', 'size_t']],
+    'khash_context_set_flags': ['int', ['pointer', 'uint16']],
+
+    'khash_context_hash': ['int', ['pointer', 'string', 'size_t', PHandle, PSize]],
+    'khash_free_string': ['int', ['pointer']],
+
+    'khash_last_error_message': ['int', ['pointer', 'size_t']],
+    'khash_strerror': ['string', ['int']],
 });
 
-const ctx_create = (algo, salt, salt_ref, salt_sz) => {
-    let ctx_ptr = ref.alloc(Context);
-    lib.khash_new_context(algo, salt, salt_ref, salt_sz, ctx_ptr);
-    return ctx_ptr;
+if (lib.khash_abi_version() !== ABI_VERSION) {
+    throw new Error(`libkhash ABI version ${lib.khash_abi_version()} is not supported (expected ${ABI_VERSION})`);
+}
+
+const check = (rc) => {
+    if (rc !== 0) throw new Error(khash_last_error_message() || lib.khash_strerror(rc));
+};
+
+const ctx_create = (algo, salt_type, salt_buffer) => {
+    let output = ref.alloc('pointer');
+    check(lib.khash_context_create(output));
+    const ctx = output.deref();
+    try {
+	check(lib.khash_context_set_algorithm(ctx, algo));
+	check(lib.khash_context_set_salt(ctx, salt_type, salt_buffer, salt_buffer ? salt_buffer.length : 0));
+    } catch (e) {
+	lib.khash_context_destroy(ctx);
+	throw e;
+    }
+    return ctx;
 };
-const ctx_free = (ptr) => {
-    lib.khash_free_context(ptr);
-    return ptr.deref();
+const ctx_free = (ctx) => {
+    check(lib.khash_context_destroy(ctx));
 };
 const ctx_clone = (src) => {
-    let dst = ref.alloc(Context);
-    lib.khash_clone_context(src,dst);
-    return dst;
+    let output = ref.alloc('pointer');
+    check(lib.khash_context_clone(src, output));
+    return output.deref();
 };
-const khash_length = (ctx, jsstring) => {
+const khash_hash = (ctx, jsstring) => {
     let string = ref.allocCString(jsstring);
-    let len = ref.alloc('long');
-    lib.khash_length(ctx, string, string.length, len);
-    return len.deref();
+    let output = ref.alloc('pointer');
+    check(lib.khash_context_hash(ctx, string, string.length, output, null));
+    const ptr = output.deref();
+    const hash = ref.readCString(ptr, 0);
+    lib.khash_free_string(ptr);
+    return hash;
 };
-const khash_do = (ctx, jsstring, len) => {
-    let string = ref.allocCString(jsstring);
+
+const khash_last_error_message = () => {
+    const len = lib.khash_last_error_message(null, 0);
     let buffer = Buffer.alloc(len+1);
-    lib.khash_do(ctx,string,string.length,buffer,len);
-    return ref.readCString(buffer,0);
-};
-const khash_max_length = (algo, input) => {
-    let len = ref.alloc('long');
-    lib.khash_max_length(algo, input, len);
-    return len.deref();
+    lib.khash_last_error_message(buffer, len+1);
+    return ref.readCString(buffer, 0);
 };
 
 const get_salt_type = (salt) => {
@@ -87,17 +106,19 @@ function Kana(algo, salt)
     } else {
 	const stype = get_salt_type(salt);
 	const fbuffer = salt ? salt.buffer || null : null;
-	this.ctx = ctx_create(algo || 0, stype, fbuffer, fbuffer ? fbuffer.length : 0);
+	this.ctx = ctx_create(algo || 0, stype, fbuffer);
     }
 }
 Kana.single = function(algo, salt, input) {
-    const mlen = khash_max_length(algo || 0, input.length);
-
     const stype = get_salt_type(salt);
     const fbuffer = salt ? salt.buffer || null : null;
-    const ctx = ctx_create(algo || 0, stype, fbuffer, fbuffer ? fbuffer.length : 0);
+    const ctx = ctx_create(algo || 0, stype, fbuffer);
 
-    return khash_do(ctx, input, mlen);
+    try {
+	return khash_hash(ctx, input);
+    } finally {
+	ctx_free(ctx);
+    }
 };
 const K = Kana.prototype;
 
@@ -110,15 +131,58 @@ K.finish = function() {
 
 /// Compute the kana-hash for `string` and then free the associated context.
 K.once = function(string) {
-    let len = khash_length(this.ctx, string);
-    return khash_do(this.ctx, string, len);
+    try {
+	return khash_hash(this.ctx, string);
+    } finally {
+	this.finish();
+    }
 };
 
 /// Compute the kana-hash for `string`.
 K.hash = function(string) {
-    const ctx = ctx_clone(this.ctx);
-    let len = khash_length(ctx, string);
-    return khash_do(ctx, string, len);
+    return khash_hash(this.ctx, string);
+};
+
+/// The message of the last error returned by the library on this thread, or an empty string.
+Kana.lastError = function() {
+    return khash_last_error_message();
+};
+
+/// A description of a library error code.
+Kana.strerror = function(code) {
+    return lib.khash_strerror(code);
+};
+
+/// Set the output flags of this context, a combination of the `Kana.FLAG_*` constants.
+K.setFlags = function(flags) {
+    check(lib.khash_context_set_flags(this.ctx, flags));
+    return this;
+};
+
+/// The version of the loaded library.
+Kana.version = function() {
+    return lib.khash_version();
+};
+
+/// The algorithms compiled into the loaded library, as objects with `id` (for the `algo` argument), `name`, `digestSize`, `cryptographic` and `extendable`.
+Kana.algorithms = function() {
+    const algorithms = [];
+    const count = lib.khash_algorithm_count();
+    for (let i = 0; i < count; i++) {
+	algorithms.push({
+	    id: lib.khash_algorithm_id(i),
+	    name: lib.khash_algorithm_name(i),
+	    digestSize: lib.khash_algorithm_digest_size(i),
+	    cryptographic: lib.khash_algorithm_cryptographic(i),
+	    extendable: lib.khash_algorithm_extendable(i),
+	});
+    }
+    return algorithms;
+};
+
+/// Is the algorithm `algo` (one of the `Kana.ALGO_*` constants) compiled into the loaded library.
+Kana.supported = function(algo) {
+    return lib.khash_algorithm_supported(algo);
 };
 
 /// Clone this kana-hash context.
@@ -137,6 +201,47 @@ Kana.ALGO_CRC64 = 2;
 Kana.ALGO_SHA256 = 3;
 /// SHA256 truncated to 64-bits.
 Kana.ALGO_SHA256_TRUNCATED = 4;
+/// HMAC-SHA256 algorithm, keyed with the salt.
+Kana.ALGO_HMAC_SHA256 = 5;
+/// HMAC-SHA256 algorithm, keyed with the salt, truncated to 64-bits.
+Kana.ALGO_HMAC_SHA256_TRUNCATED = 6;
+/// BLAKE3 algorithm.
+Kana.ALGO_BLAKE3 = 7;
+/// BLAKE3 truncated to 64-bits.
+Kana.ALGO_BLAKE3_TRUNCATED = 8;
+/// SHA512 algorithm.
+Kana.ALGO_SHA512 = 9;
+/// SHA-512/256 algorithm.
+Kana.ALGO_SHA512_256 = 10;
+/// SHA3-256 algorithm.
+Kana.ALGO_SHA3_256 = 11;
+/// xxHash3 64-bit algorithm.
+Kana.ALGO_XXH3_64 = 12;
+/// xxHash3 128-bit algorithm.
+Kana.ALGO_XXH3_128 = 13;
+/// SipHash-1-3 algorithm, keyed with the salt.
+Kana.ALGO_SIPHASH13 = 14;
+/// CRC32C (Castagnoli) algorithm.
+Kana.ALGO_CRC32C = 15;
+/// CRC-64/GO-ISO algorithm.
+Kana.ALGO_CRC64_ISO = 16;
+/// CRC-64/XZ algorithm. (Same as `ALGO_CRC64`.)
+Kana.ALGO_CRC64_XZ = Kana.ALGO_CRC64;
+/// Argon2id password hashing algorithm.
+Kana.ALGO_ARGON2ID = 17;
+/// scrypt password hashing algorithm.
+Kana.ALGO_SCRYPT = 18;
+/// PBKDF2-HMAC-SHA256 password hashing algorithm.
+Kana.ALGO_PBKDF2 = 19;
+/// SHAKE256 extendable output algorithm.
+Kana.ALGO_SHAKE256 = 20;
+
+/// Output only hiragana.
+Kana.FLAG_HIRAGANA = 1 << 0;
+/// Output only katakana.
+Kana.FLAG_KATAKANA = 1 << 1;
+/// Output a `-` between each group of 2 kana.
+Kana.FLAG_SEPARATORS = 1 << 2;
 
 // You don't need to reference these directly, use the `Salt` module instead.
 Kana.SALT_NONE = 0;
@@ -0,0 +1,113 @@
+/* eslint-disable */
+
+export type Input = string | Buffer | Uint8Array
+
+/** The algorithms, as their `KHASH_ALGO_*` values */
+export const enum Algorithm {
+  Default = 0,
+  Crc32 = 1,
+  Crc64 = 2,
+  Sha256 = 3,
+  Sha256Truncated = 4,
+  HmacSha256 = 5,
+  HmacSha256Truncated = 6,
+  Blake3 = 7,
+  Blake3Truncated = 8,
+  Sha512 = 9,
+  Sha512_256 = 10,
+  Sha3_256 = 11,
+  Xxh3_64 = 12,
+  Xxh3_128 = 13,
+  SipHash13 = 14,
+  Crc32c = 15,
+  Crc64Iso = 16,
+  Argon2id = 17,
+  Scrypt = 18,
+  Pbkdf2 = 19,
+  Shake256 = 20
+}
+/** The kana script a hash is written in */
+export const enum Script {
+  Mixed = 0,
+  Hiragana = 1,
+  Katakana = 2
+}
+/** Cost parameters for the password-hashing algorithms. `0` or missing uses the algorithm's default. */
+export interface Cost {
+  iterations?: number
+  memoryKib?: number
+  parallelism?: number
+}
+/** Options for a `Kana` context */
+export interface KanaOptions {
+  /** The algorithm to use (default `Algorithm.Default`) */
+  algorithm?: Algorithm
+  /** The salt to use, `null` for none (default is the library's static salt) */
+  salt?: string | Buffer | Uint8Array | null
+  /** Use a randomly generated salt instead of `salt` */
+  randomSalt?: boolean
+  /** The requested digest length in bytes (default is the algorithm's) */
+  length?: number
+  /** Cost parameters for the password-hashing algorithms */
+  cost?: Cost
+  /** The script to write the kana in (default `Script.Mixed`) */
+  script?: Script
+  /** Write a `-` between each group of 2 kana */
+  separators?: boolean
+}
+/** Information about an algorithm compiled into the addon */
+export interface AlgorithmInfo {
+  /** The `Algorithm` value */
+  id: number
+  name: string
+  /** The digest size in bytes when no length is requested */
+  digestSize: number
+  /** A cryptographic hash, rather than a checksum */
+  cryptographic: boolean
+  /** Can produce digests of any requested length */
+  extendable: boolean
+}
+/** The algorithms compiled into the addon */
+export function algorithms(): Array<AlgorithmInfo>
+/** Is `algorithm` compiled into the addon */
+export function supported(algorithm: number): boolean
+/** A kana-hash context */
+export class Kana {
+  /** Create a new context. With no options, the default algorithm and salt are used. */
+  constructor(options?: KanaOptions | undefined | null)
+  /** Compute the kana hash of `input` */
+  hash(input: Input): string
+  /** Compute the kana hash of `input` on the thread pool */
+  hashAsync(input: Input): Promise<string>
+  /** Compute the kana hash of the contents of the file at `path` on the thread pool */
+  hashFile(path: string): Promise<string>
+  /** Compute the kana hash of everything read from `readable`, hashing each chunk on the thread pool */
+  hashStream(readable: AsyncIterable<Input>): Promise<string>
+  /** Check the kana hash of `input` against `expected` in constant time */
+  verify(input: Input, expected: string): boolean
+  /** Start an incremental hash with this context */
+  stream(): KanaStream
+  /** The `Algorithm` value of this context */
+  get algorithm(): number
+  /** The size in bytes of the digest this context produces */
+  get digestSize(): number
+  /** The length in bytes (as UTF-8) of the kana hashes this context produces */
+  get kanaLength(): number
+}
+/**
+ * An incremental kana hash. Created with `Kana.stream()`.
+ *
+ * Only one `updateAsync()` or `digestAsync()` can be pending at a time, so chunks are always hashed in order.
+ */
+export class KanaStream {
+  /** Feed more input into the hash */
+  update(input: Input): void
+  /** Feed more input into the hash on the thread pool. `input` is copied, so it can be reused immediately. */
+  updateAsync(input: Input): Promise<void>
+  /** Finish the hash and return its kana. The stream cannot be used afterwards. */
+  digest(): string
+  /** Finish the hash on the thread pool and return its kana. The stream cannot be used afterwards. */
+  digestAsync(): Promise<string>
+  /** The number of bytes fed into the hash so far */
+  get written(): number
+}
@@ -0,0 +1,17 @@
+const { Kana, KanaStream, Algorithm, Script, algorithms, supported } = require('./khash.node');
+
+/// Hash everything read from `readable` (any async iterable of strings, `Buffer`s or `Uint8Array`s, such as a `stream.Readable`.)
+/// Each chunk is hashed on the thread pool.
+Kana.prototype.hashStream = async function(readable) {
+    const stream = this.stream();
+    for await (const chunk of readable)
+	await stream.updateAsync(chunk);
+    return stream.digestAsync();
+};
+
+exports.Kana = Kana;
+exports.KanaStream = KanaStream;
+exports.Algorithm = Algorithm;
+exports.Script = Script;
+exports.algorithms = algorithms;
+exports.supported = supported;
@@ -0,0 +1,515 @@
+//! N-API addon for kana-hash
+//!
+//! Wraps `khash::ctx::Context` directly as the JS class `Kana`, so no struct layouts are shared with JS.
+//! Input can be a string (hashed as UTF-8), a `Buffer` or a `Uint8Array`.
+#![deny(clippy::all)]
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use khash::{
+    ctx,
+    salt,
+    error,
+    Hasher,
+};
+use std::{
+    fs::File,
+    io::BufReader,
+    convert::TryFrom,
+    sync::{
+	Arc,
+	Mutex,
+	atomic::{
+	    AtomicBool,
+	    Ordering,
+	},
+    },
+};
+
+/// Input to hash
+type Input = Either3<String, Buffer, Uint8Array>;
+
+/// A salt: `null` for no salt, otherwise the salt's bytes
+type SaltInput = Either4<String, Buffer, Uint8Array, Null>;
+
+fn input_bytes(input: &Input) -> &[u8]
+{
+    match input {
+	Either3::A(string) => string.as_bytes(),
+	Either3::B(buffer) => &buffer[..],
+	Either3::C(array) => &array[..],
+    }
+}
+
+fn to_napi(error: error::Error) -> Error
+{
+    Error::new(Status::GenericFailure, error.to_string())
+}
+
+fn invalid_arg(message: impl Into<String>) -> Error
+{
+    Error::new(Status::InvalidArg, message.into())
+}
+
+/// The algorithms, as their `KHASH_ALGO_*` values
+#[napi]
+pub enum Algorithm
+{
+    Default = 0,
+    Crc32 = 1,
+    Crc64 = 2,
+    Sha256 = 3,
+    Sha256Truncated = 4,
+    HmacSha256 = 5,
+    HmacSha256Truncated = 6,
+    Blake3 = 7,
+    Blake3Truncated = 8,
+    Sha512 = 9,
+    Sha512_256 = 10,
+    Sha3_256 = 11,
+    Xxh3_64 = 12,
+    Xxh3_128 = 13,
+    SipHash13 = 14,
+    Crc32c = 15,
+    Crc64Iso = 16,
+    Argon2id = 17,
+    Scrypt = 18,
+    Pbkdf2 = 19,
+    Shake256 = 20,
+}
+
+/// The kana script a hash is written in
+#[napi]
+pub enum Script
+{
+    Mixed = 0,
+    Hiragana = 1,
+    Katakana = 2,
+}
+
+/// Find a compiled in algorithm by its `Algorithm` value
+fn algorithm(id: u32) -> Result<ctx::Algorithm>
+{
+    if id == Algorithm::Default as u32 {
+	return Ok(Default::default());
+    }
+    ctx::Algorithm::all().iter()
+	.find(|algo| u8::try_from((*algo).clone()).map(u32::from).ok() == Some(id))
+	.cloned()
+	.ok_or_else(|| invalid_arg(format!("unknown or unsupported algorithm {}", id)))
+}
+
+/// Cost parameters for the password-hashing algorithms. `0` or missing uses the algorithm's default.
+#[napi(object)]
+pub struct Cost
+{
+    pub iterations: Option<u32>,
+    pub memory_kib: Option<u32>,
+    pub parallelism: Option<u32>,
+}
+
+/// Options for a `Kana` context
+#[napi(object, object_to_js = false)]
+pub struct KanaOptions
+{
+    /// The algorithm to use (default `Algorithm.Default`)
+    pub algorithm: Option<Algorithm>,
+    /// The salt to use, `null` for none (default is the library's static salt)
+    pub salt: Option<SaltInput>,
+    /// Use a randomly generated salt instead of `salt`
+    pub random_salt: Option<bool>,
+    /// The requested digest length in bytes (default is the algorithm's)
+    pub length: Option<u32>,
+    /// Cost parameters for the password-hashing algorithms
+    pub cost: Option<Cost>,
+    /// The script to write the kana in (default `Script.Mixed`)
+    pub script: Option<Script>,
+    /// Write a `-` between each group of 2 kana
+    pub separators: Option<bool>,
+}
+
+fn context_from(options: KanaOptions) -> Result<ctx::Context>
+{
+    let algo = algorithm(options.algorithm.map(|algo| algo as u32).unwrap_or(0))?;
+    let salt = if options.random_salt.unwrap_or(false) {
+	salt::Salt::random().map_err(|e| to_napi(e.into()))?
+    } else {
+	let bytes = match &options.salt {
+	    None => return build(algo, salt::Salt::default(), options),
+	    Some(Either4::D(_)) => return build(algo, salt::Salt::none(), options),
+	    Some(Either4::A(string)) => string.as_bytes(),
+	    Some(Either4::B(buffer)) => &buffer[..],
+	    Some(Either4::C(array)) => &array[..],
+	};
+	if bytes.is_empty() {
+	    return Err(invalid_arg("salt cannot be empty, use `null` for no salt"));
+	}
+	salt::Salt::unfixed(bytes)
+    };
+    build(algo, salt, options)
+}
+
+fn build(algo: ctx::Algorithm, salt: salt::Salt, options: KanaOptions) -> Result<ctx::Context>
+{
+    let cost = options.cost.map(|cost| ctx::Cost {
+	iterations: cost.iterations.unwrap_or(0),
+	memory_kib: cost.memory_kib.unwrap_or(0),
+	parallelism: cost.parallelism.unwrap_or(0),
+    }).unwrap_or_default();
+    let output = ctx::OutputOptions {
+	script: match options.script.unwrap_or(Script::Mixed) {
+	    Script::Mixed => ctx::Script::Mixed,
+	    Script::Hiragana => ctx::Script::Hiragana,
+	    Script::Katakana => ctx::Script::Katakana,
+	},
+	separators: options.separators.unwrap_or(false),
+	..Default::default()
+    };
+    let context = ctx::Context::new_with_cost(algo, salt, cost).with_output(output);
+    Ok(match options.length {
+	Some(length) if length > 0 => context.with_length(length as usize),
+	_ => context,
+    })
+}
+
+/// Information about an algorithm compiled into the addon
+#[napi(object)]
+pub struct AlgorithmInfo
+{
+    /// The `Algorithm` value
+    pub id: u32,
+    pub name: String,
+    /// The digest size in bytes when no length is requested
+    pub digest_size: u32,
+    /// A cryptographic hash, rather than a checksum
+    pub cryptographic: bool,
+    /// Can produce digests of any requested length
+    pub extendable: bool,
+}
+
+/// The algorithms compiled into the addon
+#[napi]
+pub fn algorithms() -> Result<Vec<AlgorithmInfo>>
+{
+    ctx::Algorithm::all().iter().map(|algo| Ok(AlgorithmInfo {
+	id: u8::try_from(algo.clone()).map_err(to_napi)?.into(),
+	name: algo.name().to_owned(),
+	digest_size: algo.digest_size() as u32,
+	cryptographic: algo.is_cryptographic(),
+	extendable: algo.is_extendable(),
+    })).collect()
+}
+
+/// Is `algorithm` compiled into the addon
+#[napi]
+pub fn supported(algorithm: u32) -> bool
+{
+    self::algorithm(algorithm).is_ok()
+}
+
+/// Hash `input` in the background
+pub struct HashTask
+{
+    context: ctx::Context,
+    input: Vec<u8>,
+}
+
+impl Task for HashTask
+{
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<Self::Output>
+    {
+	khash::generate(&self.context, &self.input[..]).map_err(to_napi)
+    }
+
+    fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue>
+    {
+	Ok(output)
+    }
+}
+
+/// Hash the contents of a file in the background
+pub struct HashFileTask
+{
+    context: ctx::Context,
+    path: String,
+}
+
+impl Task for HashFileTask
+{
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<Self::Output>
+    {
+	let file = File::open(&self.path).map_err(|e| to_napi(e.into()))?;
+	let (_, hash) = khash::generate_stream(&self.context, &mut BufReader::new(file)).map_err(to_napi)?;
+	Ok(hash)
+    }
+
+    fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue>
+    {
+	Ok(output)
+    }
+}
+
+/// A kana-hash context
+#[napi]
+pub struct Kana
+{
+    context: ctx::Context,
+}
+
+#[napi]
+impl Kana
+{
+    /// Create a new context. With no options, the default algorithm and salt are used.
+    #[napi(constructor)]
+    pub fn new(options: Option<KanaOptions>) -> Result<Self>
+    {
+	Ok(Self {
+	    context: match options {
+		Some(options) => context_from(options)?,
+		None => Default::default(),
+	    },
+	})
+    }
+
+    /// Compute the kana hash of `input`
+    #[napi]
+    pub fn hash(&self, input: Input) -> Result<String>
+    {
+	khash::generate(&self.context, input_bytes(&input)).map_err(to_napi)
+    }
+
+    /// Compute the kana hash of `input` on the thread pool
+    #[napi(ts_return_type = "Promise<string>")]
+    pub fn hash_async(&self, input: Input) -> AsyncTask<HashTask>
+    {
+	AsyncTask::new(HashTask {
+	    context: self.context.clone(),
+	    input: input_bytes(&input).to_vec(),
+	})
+    }
+
+    /// Compute the kana hash of the contents of the file at `path` on the thread pool
+    #[napi(ts_return_type = "Promise<string>")]
+    pub fn hash_file(&self, path: String) -> AsyncTask<HashFileTask>
+    {
+	AsyncTask::new(HashFileTask {
+	    context: self.context.clone(),
+	    path,
+	})
+    }
+
+    /// Check the kana hash of `input` against `expected` in constant time
+    #[napi]
+    pub fn verify(&self, input: Input, expected: String) -> Result<bool>
+    {
+	khash::verify(&self.context, input_bytes(&input), &expected[..]).map_err(to_napi)
+    }
+
+    /// Start an incremental hash with this context
+    #[napi]
+    pub fn stream(&self) -> KanaStream
+    {
+	KanaStream {
+	    hasher: Arc::new(Mutex::new(Some(Hasher::new(&self.context)))),
+	    busy: Arc::new(AtomicBool::new(false)),
+	}
+    }
+
+    /// The `Algorithm` value of this context
+    #[napi(getter)]
+    pub fn algorithm(&self) -> Result<u32>
+    {
+	Ok(u8::try_from(self.context.get_algorithm().clone()).map_err(to_napi)?.into())
+    }
+
+    /// The size in bytes of the digest this context produces
+    #[napi(getter)]
+    pub fn digest_size(&self) -> u32
+    {
+	self.context.digest_size() as u32
+    }
+
+    /// The length in bytes (as UTF-8) of the kana hashes this context produces
+    #[napi(getter)]
+    pub fn kana_length(&self) -> u32
+    {
+	self.context.kana_length() as u32
+    }
+}
+
+/// The hasher of a `KanaStream`, shared with its background tasks. `None` once finished.
+type SharedHasher = Arc<Mutex<Option<Hasher>>>;
+
+fn lock(hasher: &SharedHasher) -> std::sync::MutexGuard<'_, Option<Hasher>>
+{
+    // A panic while updating can only leave the hasher with a wrong digest, not an invalid one
+    hasher.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn finished() -> Error
+{
+    invalid_arg("stream already finished")
+}
+
+/// Feed a chunk into a `KanaStream` in the background
+pub struct UpdateTask
+{
+    hasher: SharedHasher,
+    busy: Arc<AtomicBool>,
+    input: Vec<u8>,
+}
+
+impl Task for UpdateTask
+{
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output>
+    {
+	match &mut *lock(&self.hasher) {
+	    Some(hasher) => {
+		hasher.update(&self.input[..]);
+		Ok(())
+	    },
+	    None => Err(finished()),
+	}
+    }
+
+    fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue>
+    {
+	Ok(output)
+    }
+
+    fn finally(&mut self, _: Env) -> Result<()>
+    {
+	self.busy.store(false, Ordering::Release);
+	Ok(())
+    }
+}
+
+/// Finish a `KanaStream` in the background
+pub struct DigestTask
+{
+    hasher: SharedHasher,
+    busy: Arc<AtomicBool>,
+}
+
+impl Task for DigestTask
+{
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<Self::Output>
+    {
+	match lock(&self.hasher).take() {
+	    Some(hasher) => hasher.finalize().map_err(to_napi),
+	    None => Err(finished()),
+	}
+    }
+
+    fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue>
+    {
+	Ok(output)
+    }
+
+    fn finally(&mut self, _: Env) -> Result<()>
+    {
+	self.busy.store(false, Ordering::Release);
+	Ok(())
+    }
+}
+
+/// An incremental kana hash. Created with `Kana.stream()`.
+///
+/// Only one `updateAsync()` or `digestAsync()` can be pending at a time, so chunks are always hashed in order.
+#[napi]
+pub struct KanaStream
+{
+    hasher: SharedHasher,
+    busy: Arc<AtomicBool>,
+}
+
+impl KanaStream
+{
+    /// Claim the stream for an operation, failing if a background one is still pending
+    fn claim(&self) -> Result<()>
+    {
+	if self.busy.swap(true, Ordering::AcqRel) {
+	    Err(invalid_arg("stream is busy, await the pending updateAsync() or digestAsync() first"))
+	} else {
+	    Ok(())
+	}
+    }
+
+    fn idle(&self) -> Result<()>
+    {
+	self.claim()?;
+	self.busy.store(false, Ordering::Release);
+	Ok(())
+    }
+}
+
+#[napi]
+impl KanaStream
+{
+    /// Feed more input into the hash
+    #[napi]
+    pub fn update(&mut self, input: Input) -> Result<()>
+    {
+	self.idle()?;
+	match &mut *lock(&self.hasher) {
+	    Some(hasher) => {
+		hasher.update(input_bytes(&input));
+		Ok(())
+	    },
+	    None => Err(finished()),
+	}
+    }
+
+    /// Feed more input into the hash on the thread pool. `input` is copied, so it can be reused immediately.
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn update_async(&mut self, input: Input) -> Result<AsyncTask<UpdateTask>>
+    {
+	self.claim()?;
+	Ok(AsyncTask::new(UpdateTask {
+	    hasher: self.hasher.clone(),
+	    busy: self.busy.clone(),
+	    input: input_bytes(&input).to_vec(),
+	}))
+    }
+
+    /// Finish the hash and return its kana. The stream cannot be used afterwards.
+    #[napi]
+    pub fn digest(&mut self) -> Result<String>
+    {
+	self.idle()?;
+	match lock(&self.hasher).take() {
+	    Some(hasher) => hasher.finalize().map_err(to_napi),
+	    None => Err(finished()),
+	}
+    }
+
+    /// Finish the hash on the thread pool and return its kana. The stream cannot be used afterwards.
+    #[napi(ts_return_type = "Promise<string>")]
+    pub fn digest_async(&mut self) -> Result<AsyncTask<DigestTask>>
+    {
+	self.claim()?;
+	Ok(AsyncTask::new(DigestTask {
+	    hasher: self.hasher.clone(),
+	    busy: self.busy.clone(),
+	}))
+    }
+
+    /// The number of bytes fed into the hash so far
+    #[napi(getter)]
+    pub fn written(&self) -> f64
+    {
+	lock(&self.hasher).as_ref().map(|hasher| hasher.written()).unwrap_or(0) as f64
+    }
+}
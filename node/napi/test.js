@@ -0,0 +1,49 @@
+// Smoke test for the addon. Build it first with `npm run build` (or `build:debug`).
+const assert = require('assert');
+const { Readable } = require('stream');
+const { Kana } = require('./index.js');
+
+const INPUT = 'hello world, this is the kana-hash napi smoke test';
+// `khash::generate` of `INPUT` with the default context, and with `Salt::none()`
+const EXPECTED = 'ぎゃわヌけゅメュ';
+const EXPECTED_NO_SALT = 'シェどぃソァヒヂ';
+
+const chunks = () => {
+    const bytes = Buffer.from(INPUT);
+    return Readable.from([bytes.subarray(0, 5), new Uint8Array(bytes.subarray(5, 17)), bytes.subarray(17).toString()]);
+};
+
+const check = async (kana, expected) => {
+    assert.strictEqual(kana.hash(INPUT), expected);
+    assert.strictEqual(kana.hash(Buffer.from(INPUT)), expected);
+    assert.strictEqual(kana.hash(new Uint8Array(Buffer.from(INPUT))), expected);
+    assert.strictEqual(await kana.hashAsync(INPUT), expected);
+    assert.strictEqual(await kana.hashStream(chunks()), expected);
+    assert.ok(kana.verify(INPUT, expected));
+
+    const stream = kana.stream();
+    stream.update(INPUT.slice(0, 10));
+    await stream.updateAsync(INPUT.slice(10));
+    assert.strictEqual(stream.written, Buffer.byteLength(INPUT));
+    assert.strictEqual(await stream.digestAsync(), expected);
+    assert.throws(() => stream.digest());
+};
+
+(async () => {
+    await check(new Kana(), EXPECTED);
+    await check(new Kana({salt: null}), EXPECTED_NO_SALT);
+
+    // Only one background operation can be pending, so chunks cannot be hashed out of order
+    const stream = new Kana().stream();
+    const pending = stream.updateAsync(INPUT);
+    assert.throws(() => stream.updateAsync(INPUT));
+    assert.throws(() => stream.update(INPUT));
+    await pending;
+    assert.strictEqual(await stream.digestAsync(), EXPECTED);
+    await assert.rejects(stream.updateAsync(INPUT));
+
+    console.log('ok');
+})().catch((e) => {
+    console.error(e);
+    process.exit(1);
+});
@@ -0,0 +1,482 @@
+//! Python extension for kana-hash
+//!
+//! Exposes `khash::ctx::Context`, the algorithms and `khash::salt::Salt` as the classes `Context`, `Algorithm` and `Salt` in the module `kana_hash`.
+//! Input can be `bytes`, `str` (hashed as UTF-8) or any object supporting the buffer protocol, such as `bytearray` or `memoryview`.
+use pyo3::{
+    prelude::*,
+    buffer::PyBuffer,
+    create_exception,
+    exceptions::{
+	PyException,
+	PyTypeError,
+	PyValueError,
+    },
+    intern,
+    types::{
+	PyBytes,
+	PyString,
+    },
+};
+use khash::{
+    ctx,
+    salt,
+    error,
+};
+use std::{
+    io::{
+	self,
+	Read,
+    },
+    convert::TryFrom,
+};
+
+create_exception!(kana_hash, KanaHashError, PyException, "An error from the kana-hash library");
+
+/// Inputs at least this large are hashed with the GIL released
+const RELEASE_GIL_SIZE: usize = 64 * 1024;
+
+fn to_py(error: error::Error) -> PyErr
+{
+    match error {
+	error::Error::IO(io) => io.into(),
+	error => KanaHashError::new_err(error.to_string()),
+    }
+}
+
+/// Input to hash
+#[derive(FromPyObject)]
+enum Input<'py>
+{
+    Bytes(Bound<'py, PyBytes>),
+    Str(Bound<'py, PyString>),
+    Buffer(PyBuffer<u8>),
+}
+
+impl<'py> Input<'py>
+{
+    /// Call `f` with the bytes of this input. Only buffer protocol objects are copied, as they may be mutable.
+    fn with_bytes<F, T>(&self, py: Python<'py>, f: F) -> PyResult<T>
+    where F: FnOnce(&[u8]) -> PyResult<T>
+    {
+	match self {
+	    Self::Bytes(bytes) => f(bytes.as_bytes()),
+	    Self::Str(string) => f(string.to_str()?.as_bytes()),
+	    Self::Buffer(buffer) => f(&buffer.to_vec(py)?[..]),
+	}
+    }
+}
+
+/// Adapts a Python file-like object (anything with a binary `read(n)`) to `Read`.
+///
+/// The GIL is only held while calling `read`, so it can be used with the GIL released.
+struct PyReader
+{
+    file: Py<PyAny>,
+    error: Option<PyErr>,
+}
+
+impl PyReader
+{
+    fn new(file: Py<PyAny>) -> Self
+    {
+	Self {
+	    file,
+	    error: None,
+	}
+    }
+
+    /// Replace an IO error caused by a Python exception with that exception
+    fn check<T>(&mut self, result: Result<T, error::Error>) -> PyResult<T>
+    {
+	match self.error.take() {
+	    Some(error) => Err(error),
+	    None => result.map_err(to_py),
+	}
+    }
+}
+
+impl Read for PyReader
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+	let read = Python::with_gil(|py| -> PyResult<usize> {
+	    let chunk = self.file.bind(py).call_method1(intern!(py, "read"), (buf.len(),))?;
+	    let chunk = chunk.downcast::<PyBytes>().map_err(|_| PyTypeError::new_err("file must be opened in binary mode"))?.as_bytes();
+	    if chunk.len() > buf.len() {
+		return Err(PyValueError::new_err("read() returned more bytes than requested"));
+	    }
+	    buf[..chunk.len()].copy_from_slice(chunk);
+	    Ok(chunk.len())
+	});
+	read.map_err(|error| {
+	    self.error = Some(error);
+	    io::Error::other("read() raised an exception")
+	})
+    }
+}
+
+/// The algorithms, as their `KHASH_ALGO_*` values
+#[pyclass(eq, eq_int, module = "kana_hash")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Algorithm
+{
+    Default = 0,
+    Crc32 = 1,
+    Crc64 = 2,
+    Sha256 = 3,
+    Sha256Truncated = 4,
+    HmacSha256 = 5,
+    HmacSha256Truncated = 6,
+    Blake3 = 7,
+    Blake3Truncated = 8,
+    Sha512 = 9,
+    Sha512_256 = 10,
+    Sha3_256 = 11,
+    Xxh3_64 = 12,
+    Xxh3_128 = 13,
+    SipHash13 = 14,
+    Crc32c = 15,
+    Crc64Iso = 16,
+    Argon2id = 17,
+    Scrypt = 18,
+    Pbkdf2 = 19,
+    Shake256 = 20,
+}
+
+impl Algorithm
+{
+    /// The compiled in algorithm this refers to
+    fn to_ctx(self) -> PyResult<ctx::Algorithm>
+    {
+	if self == Self::Default {
+	    return Ok(Default::default());
+	}
+	ctx::Algorithm::all().iter()
+	    .find(|algo| u8::try_from((*algo).clone()).ok() == Some(self as u8))
+	    .cloned()
+	    .ok_or_else(|| PyValueError::new_err(format!("algorithm {:?} is not supported", self)))
+    }
+
+    /// Password-hashing algorithms are slow for any input
+    fn is_slow(self) -> bool
+    {
+	matches!(self, Self::Argon2id | Self::Scrypt | Self::Pbkdf2)
+    }
+}
+
+#[pymethods]
+impl Algorithm
+{
+    /// Is this algorithm compiled into the extension
+    fn supported(&self) -> bool
+    {
+	self.to_ctx().is_ok()
+    }
+
+    /// The digest size in bytes when no length is requested
+    #[getter]
+    fn digest_size(&self) -> PyResult<usize>
+    {
+	Ok(self.to_ctx()?.digest_size())
+    }
+
+    /// Is this a cryptographic hash, rather than a checksum
+    #[getter]
+    fn cryptographic(&self) -> PyResult<bool>
+    {
+	Ok(self.to_ctx()?.is_cryptographic())
+    }
+
+    /// Can this algorithm produce digests of any requested length
+    #[getter]
+    fn extendable(&self) -> PyResult<bool>
+    {
+	Ok(self.to_ctx()?.is_extendable())
+    }
+}
+
+/// The kana script a hash is written in
+#[pyclass(eq, eq_int, module = "kana_hash")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Script
+{
+    Mixed = 0,
+    Hiragana = 1,
+    Katakana = 2,
+}
+
+impl From<Script> for ctx::Script
+{
+    fn from(from: Script) -> Self
+    {
+	match from {
+	    Script::Mixed => Self::Mixed,
+	    Script::Hiragana => Self::Hiragana,
+	    Script::Katakana => Self::Katakana,
+	}
+    }
+}
+
+/// A salt for the hash. `Salt(data)` uses `data` (which cannot be empty) as the salt.
+#[pyclass(frozen, eq, module = "kana_hash")]
+#[derive(Clone, PartialEq)]
+pub struct Salt
+{
+    inner: salt::Salt,
+}
+
+#[pymethods]
+impl Salt
+{
+    #[new]
+    fn new(py: Python<'_>, data: Input<'_>) -> PyResult<Self>
+    {
+	data.with_bytes(py, |bytes| {
+	    if bytes.is_empty() {
+		return Err(PyValueError::new_err("salt cannot be empty, use `Salt.none()` for no salt"));
+	    }
+	    Ok(Self {
+		inner: salt::Salt::unfixed(bytes),
+	    })
+	})
+    }
+
+    /// No salt
+    #[staticmethod]
+    fn none() -> Self
+    {
+	Self {
+	    inner: salt::Salt::none(),
+	}
+    }
+
+    /// The library's default static salt
+    #[staticmethod]
+    fn default() -> Self
+    {
+	Self {
+	    inner: Default::default(),
+	}
+    }
+
+    /// A cryptographically secure random salt
+    #[staticmethod]
+    fn random() -> PyResult<Self>
+    {
+	Ok(Self {
+	    inner: salt::Salt::random().map_err(|e| to_py(e.into()))?,
+	})
+    }
+
+    /// The bytes of the salt
+    #[getter]
+    fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes>
+    {
+	PyBytes::new(py, self.inner.bytes())
+    }
+
+    fn __repr__(&self) -> String
+    {
+	match &self.inner {
+	    salt::Salt::None => "Salt.none()".to_owned(),
+	    salt::Salt::Static(_) => "Salt.default()".to_owned(),
+	    salt => format!("Salt({:?})", PyBytesRepr(salt.bytes())),
+	}
+    }
+}
+
+/// Formats bytes like Python's `bytes.__repr__`
+struct PyBytesRepr<'a>(&'a [u8]);
+
+impl std::fmt::Debug for PyBytesRepr<'_>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+	f.write_str("b'")?;
+	for &byte in self.0 {
+	    match byte {
+		b'\'' | b'\\' => write!(f, "\\{}", byte as char)?,
+		0x20..=0x7e => write!(f, "{}", byte as char)?,
+		_ => write!(f, "\\x{:02x}", byte)?,
+	    }
+	}
+	f.write_str("'")
+    }
+}
+
+/// A kana-hash context. With no arguments, the default algorithm and salt are used.
+#[pyclass(frozen, module = "kana_hash")]
+pub struct Context
+{
+    inner: ctx::Context,
+    algorithm: Algorithm,
+}
+
+impl Default for Context
+{
+    fn default() -> Self
+    {
+	Self {
+	    inner: Default::default(),
+	    algorithm: Algorithm::Default,
+	}
+    }
+}
+
+impl Context
+{
+    fn releases_gil(&self, size: usize) -> bool
+    {
+	size >= RELEASE_GIL_SIZE || self.algorithm.is_slow()
+    }
+
+    fn generate(&self, py: Python<'_>, bytes: &[u8]) -> PyResult<String>
+    {
+	let context = &self.inner;
+	if self.releases_gil(bytes.len()) {
+	    py.allow_threads(|| khash::generate(context, bytes))
+	} else {
+	    khash::generate(context, bytes)
+	}.map_err(to_py)
+    }
+}
+
+#[pymethods]
+impl Context
+{
+    #[new]
+    #[pyo3(signature = (algorithm = Algorithm::Default, salt = None, *, length = None, iterations = 0, memory_kib = 0, parallelism = 0, script = Script::Mixed, separators = false))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(algorithm: Algorithm,
+	   salt: Option<Salt>,
+	   length: Option<usize>,
+	   iterations: u32,
+	   memory_kib: u32,
+	   parallelism: u32,
+	   script: Script,
+	   separators: bool) -> PyResult<Self>
+    {
+	let cost = ctx::Cost {
+	    iterations,
+	    memory_kib,
+	    parallelism,
+	};
+	let output = ctx::OutputOptions {
+	    script: script.into(),
+	    separators,
+	    ..Default::default()
+	};
+	let salt = salt.map(|salt| salt.inner).unwrap_or_default();
+	let inner = ctx::Context::new_with_cost(algorithm.to_ctx()?, salt, cost).with_output(output);
+	Ok(Self {
+	    inner: match length {
+		Some(0) => return Err(PyValueError::new_err("length cannot be 0")),
+		Some(length) => inner.with_length(length),
+		None => inner,
+	    },
+	    algorithm,
+	})
+    }
+
+    /// Compute the kana hash of `data`
+    fn hash(&self, py: Python<'_>, data: Input<'_>) -> PyResult<String>
+    {
+	data.with_bytes(py, |bytes| self.generate(py, bytes))
+    }
+
+    /// Compute the kana hash of everything read from the binary file-like object `file`, with the GIL released between reads
+    fn hash_file(&self, py: Python<'_>, file: Py<PyAny>) -> PyResult<String>
+    {
+	let context = &self.inner;
+	let mut reader = PyReader::new(file);
+	let result = py.allow_threads(|| khash::generate_stream(context, &mut reader));
+	Ok(reader.check(result)?.1)
+    }
+
+    /// Check the kana hash of `data` against `expected` in constant time
+    fn verify(&self, py: Python<'_>, data: Input<'_>, expected: &str) -> PyResult<bool>
+    {
+	let context = &self.inner;
+	data.with_bytes(py, |bytes| {
+	    if self.releases_gil(bytes.len()) {
+		py.allow_threads(|| khash::verify(context, bytes, expected))
+	    } else {
+		khash::verify(context, bytes, expected)
+	    }.map_err(to_py)
+	})
+    }
+
+    /// Check the kana hash of everything read from the binary file-like object `file` against `expected` in constant time
+    fn verify_file(&self, py: Python<'_>, file: Py<PyAny>, expected: &str) -> PyResult<bool>
+    {
+	let context = &self.inner;
+	let mut reader = PyReader::new(file);
+	let result = py.allow_threads(|| khash::verify_stream(context, &mut reader, expected));
+	Ok(reader.check(result)?.1)
+    }
+
+    /// The algorithm of this context
+    #[getter]
+    fn algorithm(&self) -> Algorithm
+    {
+	self.algorithm
+    }
+
+    /// The salt of this context
+    #[getter]
+    fn salt(&self) -> Salt
+    {
+	Salt {
+	    inner: self.inner.get_salt().clone(),
+	}
+    }
+
+    /// The size in bytes of the digest this context produces
+    #[getter]
+    fn digest_size(&self) -> usize
+    {
+	self.inner.digest_size()
+    }
+
+    /// The length in bytes (as UTF-8) of the kana hashes this context produces
+    #[getter]
+    fn kana_length(&self) -> usize
+    {
+	self.inner.kana_length()
+    }
+
+    fn __repr__(&self) -> String
+    {
+	format!("Context(Algorithm.{:?}, {})", self.algorithm, self.salt().__repr__())
+    }
+}
+
+/// Compute the kana hash of `data` with `context`, or the default context
+#[pyfunction]
+#[pyo3(signature = (data, context = None))]
+fn generate(py: Python<'_>, data: Input<'_>, context: Option<&Context>) -> PyResult<String>
+{
+    let default;
+    let context = match context {
+	Some(context) => context,
+	None => {
+	    default = Context::default();
+	    &default
+	},
+    };
+    data.with_bytes(py, |bytes| context.generate(py, bytes))
+}
+
+/// Kana hashes
+#[pymodule]
+fn kana_hash(m: &Bound<'_, PyModule>) -> PyResult<()>
+{
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("KanaHashError", m.py().get_type::<KanaHashError>())?;
+    m.add_class::<Algorithm>()?;
+    m.add_class::<Script>()?;
+    m.add_class::<Salt>()?;
+    m.add_class::<Context>()?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    Ok(())
+}
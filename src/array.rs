@@ -14,3 +14,16 @@ where T: Clone,
     }
     i
 }
+
+/// Compare a known slice `a` with an untrusted slice `b` without short-circuiting.
+///
+/// Every element of `a` is compared (with `0` past the end of `b`) and a length mismatch is only folded into the result, so the time taken depends on the length of `a` alone.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool
+{
+    let mut diff = (a.len() != b.len()) as u8;
+    for (i, x) in a.iter().enumerate()
+    {
+	diff |= x ^ b.get(i).copied().unwrap_or(0);
+    }
+    unsafe { std::ptr::read_volatile(&diff) == 0 }
+}
@@ -1,20 +1,39 @@
 //! FFI exported functions
 use super::*;
+use std::ffi::CStr;
 
+/// Borrow an input buffer passed over FFI without copying it.
+///
+/// A null `bin` is an `Error::NullPointer`, and a `sz` of `0` is an `Error::EmptyInput`.
+unsafe fn input_slice<'a>(bin: *const c_void, sz: size_t) -> Result<&'a [u8], error::Error>
+{
+    if bin.is_null() {
+	Err(error::Error::NullPointer("data"))
+    } else if sz == 0 {
+	Err(error::Error::EmptyInput)
+    } else {
+	Ok(std::slice::from_raw_parts(bin as *const u8, sz))
+    }
+}
 
 /// Calculate the length in bytes of a kana hash output.
 ///
 /// # Note
 /// Does not consume `salt`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed.
+/// `bin` must be valid for reads of `sz` bytes, and `out_len` must be `NULL` or valid for writes.
 #[no_mangle]
 pub unsafe extern "C" fn khash_length(context: *const c_void, bin: *const c_void, sz: size_t, out_len: *mut size_t) -> i32
 {
     let context = context as *const ctx::CContext;
     no_unwind!{
 	try error::Error::Unknown;
-	let context = ctx::Context::clone_from_raw(context);
-	let bin = HeapArray::<u8>::from_raw_copied(bin as *const u8, usize::from(sz));
-	let string = c_try!(generate(&context, &bin));
+	c_nonnull!(out_len, "length");
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	let bin = c_try!(input_slice(bin, sz));
+	let string = c_try!(generate(&context, bin));
 	*out_len = string.bytes().len().into();
 
 	GENERIC_SUCCESS
@@ -25,6 +44,10 @@ pub unsafe extern "C" fn khash_length(context: *const c_void, bin: *const c_void
 ///
 /// # Note
 /// Consumes `salt`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed. It is freed by this call, and must not be used or freed again.
+/// `bin` must be valid for reads of `sz` bytes, and `out_str` must be `NULL` or valid for writes of `str_len` bytes.
 #[no_mangle]
 pub unsafe extern "C" fn khash_do(context: *mut c_void, bin: *const c_void, sz: size_t, out_str: *mut c_char, str_len: size_t) -> i32
 {
@@ -32,135 +55,898 @@ pub unsafe extern "C" fn khash_do(context: *mut c_void, bin: *const c_void, sz:
     no_unwind!{
 	try error::Error::Unknown;
 	
-	let context = ctx::Context::from_raw(context);
-	let bin = HeapArray::<u8>::from_raw_copied(bin as *const u8, usize::from(sz));
-	let string: Vec<u8> = c_try!(generate(&context, &bin)).bytes().collect();
-	
-	libc::memcpy(out_str as *mut c_void, &string[0] as *const u8 as *const c_void, std::cmp::min(str_len, string.len()));
+	let context = c_try!(ctx::Context::from_raw(context));
+	let bin = c_try!(input_slice(bin, sz));
+	let string = c_try!(generate(&context, bin));
+	c_try!(write_output(&string[..], out_str, str_len, std::ptr::null_mut(), context.get_output().nul_terminator));
 	
 	GENERIC_SUCCESS
     }
 }
 
+/// Compute a kana hash output into a newly allocated `NUL` terminated string.
+///
+/// # Note
+/// Does not consume `context`. The output must be freed with `khash_free_string`.
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed.
+/// `bin` must be valid for reads of `sz` bytes, and `out_str` and `out_len` must each be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_hash(context: *const c_void, bin: *const c_void, sz: size_t, out_str: *mut *mut c_char, out_len: *mut size_t) -> i32
+{
+    let context = context as *const ctx::CContext;
+    no_unwind!{
+	try error::Error::Unknown;
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	hash_to_new_string(&context, bin, sz, out_str, out_len)
+    }
+}
+
+/// Compute a kana hash output into a newly allocated `NUL` terminated string, for `khash_hash` and `khash_context_hash`.
+unsafe fn hash_to_new_string(context: &ctx::Context, bin: *const c_void, sz: size_t, out_str: *mut *mut c_char, out_len: *mut size_t) -> i32
+{
+    c_nonnull!(out_str, "string");
+    let bin = c_try!(input_slice(bin, sz));
+    let string = c_try!(generate(context, bin));
+
+    let output = libc::malloc(string.len() + 1) as *mut c_char;
+    if output.is_null() {
+	return generic_error("failed to allocate output string");
+    }
+    if let Err(e) = write_output(&string[..], output, string.len() + 1, out_len, true) {
+	libc::free(output as *mut c_void);
+	return error_code(e);
+    }
+    *out_str = output;
+
+    GENERIC_SUCCESS
+}
+
+/// Free a string allocated by `khash_hash` or `khash_context_hash`
+///
+/// # Safety
+/// `string` must be `NULL` or a string from `khash_hash` or `khash_context_hash` that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn khash_free_string(string: *mut c_char) -> i32
+{
+    no_unwind!{
+	libc::free(string as *mut c_void);
+	GENERIC_SUCCESS
+    }
+}
+
+/// Check a kana hash against an input.
+///
+/// # Note
+/// Does not consume `context`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed.
+/// `bin` must be valid for reads of `sz` bytes, `expected` must be `NULL` or a `NUL` terminated string, and `valid` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_verify(context: *const c_void, bin: *const c_void, sz: size_t, expected: *const c_char, valid: *mut bool) -> i32
+{
+    let context = context as *const ctx::CContext;
+    let expected = string_from_ffi!(expected);
+    no_unwind!{
+	try error::Error::Unknown;
+	c_nonnull!(valid, "valid");
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	let bin = c_try!(input_slice(bin, sz));
+	*valid = c_try!(verify(&context, bin, &expected[..]));
+
+	GENERIC_SUCCESS
+    }
+}
+
+/// Check a kana hash against everything read from a file descriptor.
+///
+/// # Note
+/// Does not consume `context` or close `fd`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed, and `fd` must be an open file descriptor that is not used elsewhere while this runs.
+/// `expected` must be `NULL` or a `NUL` terminated string, `read` must be `NULL` or valid for writes, and `valid` must be `NULL` or valid for writes.
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn khash_verify_stream(context: *const c_void, fd: libc::c_int, expected: *const c_char, read: *mut size_t, valid: *mut bool) -> i32
+{
+    use std::{
+	os::unix::io::FromRawFd,
+	mem::ManuallyDrop,
+	fs::File,
+    };
+    let context = context as *const ctx::CContext;
+    let expected = string_from_ffi!(expected);
+    no_unwind!{
+	try error::Error::Unknown;
+	c_nonnull!(valid, "valid");
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	let mut file = ManuallyDrop::new(File::from_raw_fd(fd));
+	let (rd, ok) = c_try!(verify_stream(&context, &mut *file, &expected[..]));
+	if !read.is_null() {
+	    *read = rd;
+	}
+	*valid = ok;
+
+	GENERIC_SUCCESS
+    }
+}
+
+/// Write as much of a kana hash output as fits in `str_len` bytes of `out_str` without splitting a kana, followed by a `NUL` terminator if `nul`.
+/// The terminator is always left room for, so a truncated output is still terminated.
+/// The full length of the output (not including a terminator) is written to `out_len` if it is not null.
+///
+/// # Errors
+/// `Error::BufferTooSmall` if the output, and the terminator if `nul`, did not fit. (`out_str` may be null to only find the length.)
+unsafe fn write_output(string: &str, out_str: *mut c_char, str_len: size_t, out_len: *mut size_t, nul: bool) -> Result<(), error::Error>
+{
+    if !out_len.is_null() {
+	*out_len = string.len();
+    }
+    let str_len = if out_str.is_null() { 0 } else { str_len };
+    let room = if nul { str_len.saturating_sub(1) } else { str_len };
+    let mut written = std::cmp::min(room, string.len());
+    while !string.is_char_boundary(written) {
+	written -= 1;
+    }
+    if written > 0 {
+	std::ptr::copy_nonoverlapping(string.as_ptr(), out_str as *mut u8, written);
+    }
+    if nul && str_len > 0 {
+	*out_str.add(written) = 0;
+    }
+    if written < string.len() {
+	Err(error::Error::BufferTooSmall{required: string.len() + usize::from(nul), got: str_len})
+    } else {
+	Ok(())
+    }
+}
+
+/// Compute a kana hash from everything read from a file descriptor and write it to a string.
+///
+/// # Note
+/// Does not consume `context` or close `fd`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed, and `fd` must be an open file descriptor that is not used elsewhere while this runs.
+/// `out_str` must be `NULL` or valid for writes of `str_len` bytes, and `read` and `out_len` must each be `NULL` or valid for writes.
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn khash_do_fd(context: *const c_void, fd: libc::c_int, out_str: *mut c_char, str_len: size_t, read: *mut size_t, out_len: *mut size_t) -> i32
+{
+    use std::{
+	os::unix::io::FromRawFd,
+	mem::ManuallyDrop,
+	fs::File,
+    };
+    let context = context as *const ctx::CContext;
+    no_unwind!{
+	try error::Error::Unknown;
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	let mut file = ManuallyDrop::new(File::from_raw_fd(fd));
+	let (rd, string) = c_try!(generate_stream(&context, &mut *file));
+	if !read.is_null() {
+	    *read = rd;
+	}
+	c_try!(write_output(&string[..], out_str, str_len, out_len, context.get_output().nul_terminator));
+
+	GENERIC_SUCCESS
+    }
+}
+
+/// Compute a kana hash from the contents of a file and write it to a string.
+///
+/// # Note
+/// Does not consume `context`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed, and `path` must be `NULL` or a `NUL` terminated string.
+/// `out_str` must be `NULL` or valid for writes of `str_len` bytes, and `read` and `out_len` must each be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_do_path(context: *const c_void, path: *const c_char, out_str: *mut c_char, str_len: size_t, read: *mut size_t, out_len: *mut size_t) -> i32
+{
+    let context = context as *const ctx::CContext;
+    let path = string_from_ffi!(path);
+    no_unwind!{
+	try error::Error::Unknown;
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	let mut file = c_try!(std::fs::File::open(&path[..]).map_err(error::Error::from));
+	let (rd, string) = c_try!(generate_stream(&context, &mut file));
+	if !read.is_null() {
+	    *read = rd;
+	}
+	c_try!(write_output(&string[..], out_str, str_len, out_len, context.get_output().nul_terminator));
+
+	GENERIC_SUCCESS
+    }
+}
+
+/// A read callback passed through FFI.
+///
+/// Returns the number of bytes written into the buffer, `0` at the end of the input, or a negative value on error.
+type ReadCallback = extern "C" fn(userdata: *mut c_void, buffer: *mut c_void, size: size_t) -> isize;
+
+/// Drives a `ReadCallback` as a `Read`.
+struct CallbackReader
+{
+    callback: ReadCallback,
+    userdata: *mut c_void,
+}
+
+impl Read for CallbackReader
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>
+    {
+	match (self.callback)(self.userdata, buf.as_mut_ptr() as *mut c_void, buf.len()) {
+	    read if read < 0 => Err(std::io::Error::other(format!("read callback failed ({})", read))),
+	    read => Ok(std::cmp::min(read as usize, buf.len())),
+	}
+    }
+}
+
+/// Compute a kana hash from everything read from a callback and write it to a string.
+///
+/// # Note
+/// Does not consume `context`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed.
+/// `callback` must be `NULL` or safe to call with `userdata` and a buffer of the size it is given, and must not unwind.
+/// `out_str` must be `NULL` or valid for writes of `str_len` bytes, and `read` and `out_len` must each be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_do_reader(context: *const c_void, callback: Option<ReadCallback>, userdata: *mut c_void, out_str: *mut c_char, str_len: size_t, read: *mut size_t, out_len: *mut size_t) -> i32
+{
+    let context = context as *const ctx::CContext;
+    let callback = match callback {
+	Some(callback) => callback,
+	None => return error_code(error::Error::NullPointer("callback")),
+    };
+    no_unwind!{
+	try error::Error::Unknown;
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	let mut reader = CallbackReader{callback, userdata};
+	let (rd, string) = c_try!(generate_stream(&context, &mut reader));
+	if !read.is_null() {
+	    *read = rd;
+	}
+	c_try!(write_output(&string[..], out_str, str_len, out_len, context.get_output().nul_terminator));
+
+	GENERIC_SUCCESS
+    }
+}
+
 /// Free a context
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed. It must not be used or freed again afterwards.
 #[no_mangle]
 pub unsafe extern "C" fn khash_free_context(context: *mut c_void) -> i32
 {
     let context = context as *mut ctx::CContext;
     no_unwind!{
-	drop(ctx::Context::from_raw(context));
+	drop(c_try!(ctx::Context::from_raw(context)));
 	GENERIC_SUCCESS
     }
 }
 
+/// Create a salt for a new context
+///
+/// # Errors
+/// A specific salt must be non-null, non-empty and no larger than `salt::MAX_FFI_SALT_SIZE`, and unknown salt types are rejected.
+unsafe fn new_context_salt(salt_type: u8, bin: *const c_void, sz: size_t) -> Result<salt::Salt, error::Error>
+{
+    Ok(match salt_type {
+	salt::SALT_TYPE_SPECIFIC => {
+	    salt::check_specific(bin as *const u8, sz)?;
+	    salt::Salt::unfixed(std::slice::from_raw_parts(bin as *const u8, sz))
+	},
+	salt::SALT_TYPE_DEFAULT => {
+	    salt::Salt::default()
+	},
+	salt::SALT_TYPE_RANDOM => {
+	    salt::Salt::random()?
+	},
+	salt::SALT_TYPE_NONE => {
+	    salt::Salt::None
+	},
+	salt_type => return Err(error::Error::UnknownSaltType(salt_type)),
+    })
+}
+
 /// Create a new context
+///
+/// # Safety
+/// If `salt_type` is `KHASH_SALT_TYPE_SPECIFIC`, `bin` must be valid for reads of `sz` bytes.
+/// `nptr` must be `NULL` or valid for writes of a `khash_ctx`.
 #[no_mangle]
 pub unsafe extern "C" fn khash_new_context(algo: u8, salt_type: u8, bin: *const c_void, sz: size_t, nptr: *mut c_void) -> i32
 {
     let nptr = nptr as *mut ctx::CContext;
     no_unwind!{
 	try error::Error::Unknown;
-	let salt = match salt_type {
-	    salt::SALT_TYPE_SPECIFIC => {
-		let bin = HeapArray::<u8>::from_raw_copied(bin as *const u8, usize::from(sz));
-		salt::Salt::unfixed(&bin[..])
-	    },
-	    salt::SALT_TYPE_DEFAULT => {
-		salt::Salt::default()
-	    },
-	    salt::SALT_TYPE_RANDOM => {
-		match salt::Salt::random() {
-		    Ok(v) => v,
-		    Err(e) => return i32::from(error::Error::RNG(e)),
-		}
-	    },
-	    _ => {
-		salt::Salt::None
-	    },
-	};
-	let context = ctx::Context::new(algo.into(), salt);
-	*nptr = context.into_raw();
+	c_nonnull!(nptr, "output");
+	let salt = c_try!(new_context_salt(salt_type, bin, sz));
+	let context = ctx::Context::new(c_try!(ctx::Algorithm::from_ffi(algo)), salt);
+	*nptr = c_try!(context.into_raw());
+	GENERIC_SUCCESS
+    }
+}
+
+/// Create a new context with cost parameters for the password-hashing algorithms
+///
+/// # Safety
+/// If `salt_type` is `KHASH_SALT_TYPE_SPECIFIC`, `bin` must be valid for reads of `sz` bytes.
+/// `nptr` must be `NULL` or valid for writes of a `khash_ctx`.
+#[no_mangle]
+pub unsafe extern "C" fn khash_new_context_with_cost(algo: u8, salt_type: u8, bin: *const c_void, sz: size_t, iterations: u32, memory_kib: u32, parallelism: u32, nptr: *mut c_void) -> i32
+{
+    let nptr = nptr as *mut ctx::CContext;
+    no_unwind!{
+	try error::Error::Unknown;
+	c_nonnull!(nptr, "output");
+	let cost = ctx::Cost{iterations, memory_kib, parallelism};
+	if !cost.is_ffi_compatible() {
+	    return error_code(error::Error::Parameters(format!("cost {:?} cannot be represented in context flags", cost)));
+	}
+	let salt = c_try!(new_context_salt(salt_type, bin, sz));
+	let context = ctx::Context::new_with_cost(c_try!(ctx::Algorithm::from_ffi(algo)), salt, cost);
+	*nptr = c_try!(context.into_raw());
 	GENERIC_SUCCESS
     }
 }
 
 
 /// Clone a context
+///
+/// # Safety
+/// `raw` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed.
+/// `out` must be `NULL` or valid for writes of a `khash_ctx`.
 #[no_mangle]
 pub unsafe extern "C" fn khash_clone_context(raw: *const c_void, out: *mut c_void) -> i32
 {
     let raw = raw as *const ctx::CContext;
     let out = out as *mut ctx::CContext;
     no_unwind!{
-	*out = ctx::Context::clone_from_raw(raw).into_raw();
+	c_nonnull!(out, "dst");
+	*out = c_try!(c_try!(ctx::Context::clone_from_raw(raw)).into_raw());
 	GENERIC_SUCCESS
     }   
 }
 
 /// Free a salt allocated with `khash_new_salt`
+///
+/// # Safety
+/// `salt` must be `NULL` or point to a `khash_salt` initialised by `khash_new_salt` that has not been freed. It must not be used or freed again afterwards.
 #[no_mangle]
 pub unsafe extern "C" fn khash_free_salt(salt: *mut c_void) -> i32
 {
     let salt = salt as *mut salt::FFI;
     no_unwind!{
-	drop(salt::from_raw(salt));
+	c_nonnull!(salt, "salt");
+	drop(c_try!(salt::from_raw(salt)));
 	GENERIC_SUCCESS
     }
 }
 
 /// Create a new salt
+///
+/// # Safety
+/// If `salt_type` is `KHASH_SALT_TYPE_SPECIFIC`, `bin` must be valid for reads of `sz` bytes.
+/// `nptr` must be `NULL` or valid for writes of a `khash_salt`.
 #[no_mangle]
 pub unsafe extern "C" fn khash_new_salt(salt_type: u8, bin: *const c_void, sz: size_t, nptr: *mut c_void) -> i32
 {
     let nptr = nptr as *mut salt::FFI;
     no_unwind!{
 	try error::Error::Unknown;
-	match salt_type {
-	    salt::SALT_TYPE_SPECIFIC => {
-		let bin = HeapArray::<u8>::from_raw_copied(bin as *const u8, usize::from(sz));
-		*nptr = salt::into_raw(salt::Salt::unfixed(&bin[..]));
-	    },
-	    salt::SALT_TYPE_DEFAULT => {
-		*nptr = salt::into_raw(salt::Salt::default());
-	    },
-	    salt::SALT_TYPE_RANDOM => {
-		*nptr = salt::into_raw(match salt::Salt::random() {
-		    Ok(v) => v,
-		    Err(e) => return i32::from(error::Error::RNG(e)),
-		})
-	    },
-	    _ => {
-		*nptr = salt::into_raw(salt::Salt::None);
-	    },
-	}
+	c_nonnull!(nptr, "output");
+	*nptr = salt::into_raw(c_try!(new_context_salt(salt_type, bin, sz)));
 	GENERIC_SUCCESS
     }
 }
 
 /// Clone a salt
+///
+/// # Safety
+/// `salt` must be `NULL` or point to a `khash_salt` initialised by `khash_new_salt` that has not been freed.
+/// `out` must be `NULL` or valid for writes of a `khash_salt`.
 #[no_mangle]
 pub unsafe extern "C" fn khash_clone_salt(salt: *const c_void, out: *mut c_void) -> i32
 {
     let salt = salt as *const salt::FFI;
     let out = out as *mut salt::FFI;
     no_unwind!{
-	*out = salt::into_raw(salt::clone_from_raw(salt));
+	c_nonnull!(salt, "src");
+	c_nonnull!(out, "dst");
+	*out = salt::into_raw(c_try!(salt::clone_from_raw(salt)));
 	GENERIC_SUCCESS
     }   
 }
 
-/// Find the maximum length possible for a given algorithm's output.
+/// Find the maximum length possible for a given algorithm's output, with any requested digest length and output options.
+///
+/// Extendable algorithms can be asked for up to `ctx::MAX_FFI_LENGTH` bytes of digest, so their bound is large. `khash_context_max_length` gives the exact length for a context.
+///
+/// # Safety
+/// `max_len` must be `NULL` or valid for writes.
 #[no_mangle]
 pub unsafe extern "C" fn khash_max_length(algo: u8, _input_sz: libc::size_t, max_len: *mut libc::size_t) -> i32
 {
     no_unwind!{
-	let hash_sz = match ctx::Algorithm::from(algo) {
-	    #[cfg(feature="crc")] ctx::Algorithm::Crc32 => std::mem::size_of::<hash::Crc32Checksum>(),
-	    #[cfg(feature="crc")] ctx::Algorithm::Crc64 => std::mem::size_of::<hash::Crc64Checksum>(),
-	    ctx::Algorithm::Sha256 => std::mem::size_of::<hash::Sha256Hash>(),
-	    ctx::Algorithm::Sha256Truncated => std::mem::size_of::<hash::Sha256Truncated>(),
+	c_nonnull!(max_len, "digest_length");
+	let algo = c_try!(ctx::Algorithm::from_ffi(algo));
+	let hash_sz = if algo.is_extendable() { ctx::MAX_FFI_LENGTH } else { algo.digest_size() };
+	*max_len = ctx::OutputOptions{separators: true, ..Default::default()}.kana_length(hash_sz);
+	GENERIC_SUCCESS
+    }
+}
+
+/// Find the maximum length possible for a context's output, taking its requested digest length into account.
+///
+/// # Note
+/// Does not consume `context`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed.
+/// `max_len` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_max_length(context: *const c_void, _input_sz: libc::size_t, max_len: *mut libc::size_t) -> i32
+{
+    let context = context as *const ctx::CContext;
+    no_unwind!{
+	c_nonnull!(max_len, "digest_length");
+	*max_len = c_try!(ctx::Context::clone_from_raw(context)).kana_length();
+	GENERIC_SUCCESS
+    }
+}
+
+use std::panic::AssertUnwindSafe;
+
+/// FFI streaming state. `None` once finished.
+type CStream = Option<Hasher>;
+
+/// Create a new streaming hash state for a context
+///
+/// # Note
+/// Does not consume `context`
+///
+/// # Safety
+/// `context` must be `NULL` or point to a `khash_ctx` initialised by `khash_new_context` that has not been freed.
+/// `stream` must be `NULL` or valid for writes. The state written there must be freed with `khash_stream_free`.
+#[no_mangle]
+pub unsafe extern "C" fn khash_stream_new(context: *const c_void, stream: *mut *mut c_void) -> i32
+{
+    let context = context as *const ctx::CContext;
+    no_unwind!{
+	c_nonnull!(stream, "stream");
+	let context = c_try!(ctx::Context::clone_from_raw(context));
+	let state: Box<CStream> = Box::new(Some(Hasher::new(&context)));
+	*stream = Box::into_raw(state) as *mut c_void;
+	GENERIC_SUCCESS
+    }
+}
+
+/// Feed more input into a streaming hash state
+///
+/// # Safety
+/// `stream` must be `NULL` or a state from `khash_stream_new` or `khash_context_stream_new` that has not been freed, and must not be used from another thread during the call.
+/// `bin` must be valid for reads of `sz` bytes unless `sz` is `0`.
+#[no_mangle]
+pub unsafe extern "C" fn khash_stream_update(stream: *mut c_void, bin: *const c_void, sz: size_t) -> i32
+{
+    // A panic can only leave the hasher with a wrong digest, not an invalid one
+    let stream = AssertUnwindSafe(stream as *mut CStream);
+    no_unwind!{
+	try error::Error::Unknown;
+	let stream = stream.0;
+	c_nonnull!(stream, "stream");
+	let hasher = match &mut *stream {
+	    Some(hasher) => hasher,
+	    None => return error_code(error::Error::Parameters("stream already finished".to_owned())),
 	};
-	*max_len =  std::mem::size_of::<char>() * hash_sz;
+	if sz > 0 {
+	    hasher.update(c_try!(input_slice(bin, sz)));
+	}
 	GENERIC_SUCCESS
     }
 }
+
+/// Finish a streaming hash state and write the kana hash output to a string.
+///
+/// # Note
+/// Does not free `stream`, but it cannot be updated or finished again.
+///
+/// # Safety
+/// `stream` must be `NULL` or a state from `khash_stream_new` or `khash_context_stream_new` that has not been freed, and must not be used from another thread during the call.
+/// `out_str` must be `NULL` or valid for writes of `str_len` bytes, and `out_len` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_stream_finish(stream: *mut c_void, out_str: *mut c_char, str_len: size_t, out_len: *mut size_t) -> i32
+{
+    // A panic can only leave the hasher with a wrong digest, not an invalid one
+    let stream = AssertUnwindSafe(stream as *mut CStream);
+    no_unwind!{
+	try error::Error::Unknown;
+	let stream = stream.0;
+	c_nonnull!(stream, "stream");
+	let hasher = match (*stream).take() {
+	    Some(hasher) => hasher,
+	    None => return error_code(error::Error::Parameters("stream already finished".to_owned())),
+	};
+	let nul = hasher.get_context().get_output().nul_terminator;
+	let string = c_try!(hasher.finalize());
+	c_try!(write_output(&string[..], out_str, str_len, out_len, nul));
+	GENERIC_SUCCESS
+    }
+}
+
+/// Free a streaming hash state
+///
+/// # Safety
+/// `stream` must be `NULL` or a state from `khash_stream_new` or `khash_context_stream_new` that has not been freed, and must not be used from another thread during the call. It must not be used or freed again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn khash_stream_free(stream: *mut c_void) -> i32
+{
+    // A panic can only leave the hasher with a wrong digest, not an invalid one
+    let stream = AssertUnwindSafe(stream as *mut CStream);
+    no_unwind!{
+	let stream = stream.0;
+	if !stream.is_null() {
+	    drop(Box::from_raw(stream));
+	}
+	GENERIC_SUCCESS
+    }
+}
+
+/// Write the message of the last error returned on this thread into `buf`, truncated on a character boundary to fit in `len` bytes including a `NUL` terminator.
+///
+/// Returns the full length of the message in bytes, not including the `NUL` terminator (`0` if there has been no error.)
+///
+/// # Safety
+/// `buf` must be `NULL` or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_last_error_message(buf: *mut c_char, len: size_t) -> i32
+{
+    no_unwind!{
+	let message = last_error().unwrap_or_default();
+	if !buf.is_null() && len > 0 {
+	    let mut written = std::cmp::min(len - 1, message.len());
+	    while !message.is_char_boundary(written) {
+		written -= 1;
+	    }
+	    std::ptr::copy_nonoverlapping(message.as_ptr(), buf as *mut u8, written);
+	    *buf.add(written) = 0;
+	}
+	std::cmp::min(message.len(), i32::MAX as usize) as i32
+    }
+}
+
+/// Get a static `NUL` terminated description of an error code.
+#[no_mangle]
+pub extern "C" fn khash_strerror(code: i32) -> *const c_char
+{
+    let message: &'static [u8] = match code {
+	GENERIC_SUCCESS => b"success\0",
+	1 => b"io error\0",
+	2 => b"formatting error\0",
+	3 => b"length mismatch\0",
+	4 => b"random number generator failed\0",
+	5 => b"invalid kana\0",
+	6 => b"input was truncated\0",
+	7 => b"invalid parameters\0",
+	8 => b"buffer too small\0",
+	9 => b"null pointer\0",
+	10 => b"empty input\0",
+	11 => b"unknown algorithm\0",
+	12 => b"unknown salt type\0",
+	13 => b"salt too large\0",
+	14 => b"empty salt\0",
+	GENERIC_ERROR => b"unknown error\0",
+	_ => b"unrecognised error code\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+/// The version of the exported C ABI.
+///
+/// Bumped whenever an exported function or struct changes incompatibly.
+pub(crate) const ABI_VERSION: u32 = 1;
+
+/// Get the version of the exported C ABI (`KHASH_ABI_VERSION` in the header this library was built with.)
+#[no_mangle]
+pub extern "C" fn khash_abi_version() -> u32
+{
+    ABI_VERSION
+}
+
+// Opaque context handles.
+//
+// These are a heap-allocated `ctx::Context`, so their layout is not part of the ABI.
+// Contexts created through FFI never hold a custom provider, so a panic cannot leave one in an invalid state.
+
+/// Create a new opaque context handle with the default algorithm and salt
+///
+/// # Safety
+/// `output` must be `NULL` or valid for writes. The handle written there must be freed with `khash_context_destroy`.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_create(output: *mut *mut c_void) -> i32
+{
+    no_unwind!{
+	c_nonnull!(output, "context");
+	*output = Box::into_raw(Box::new(ctx::Context::default())) as *mut c_void;
+	GENERIC_SUCCESS
+    }
+}
+
+/// Clone an opaque context handle
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed.
+/// `output` must be `NULL` or valid for writes. The handle written there must be freed with `khash_context_destroy`.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_clone(context: *const c_void, output: *mut *mut c_void) -> i32
+{
+    let context = AssertUnwindSafe(context as *const ctx::Context);
+    no_unwind!{
+	let context = context.0;
+	c_nonnull!(context, "src");
+	c_nonnull!(output, "dst");
+	*output = Box::into_raw(Box::new((*context).clone())) as *mut c_void;
+	GENERIC_SUCCESS
+    }
+}
+
+/// Free an opaque context handle
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed, and must not be used from another thread during the call. It must not be used or destroyed again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_destroy(context: *mut c_void) -> i32
+{
+    let context = AssertUnwindSafe(context as *mut ctx::Context);
+    no_unwind!{
+	let context = context.0;
+	if !context.is_null() {
+	    drop(Box::from_raw(context));
+	}
+	GENERIC_SUCCESS
+    }
+}
+
+/// Set the algorithm of an opaque context handle
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed, and must not be used from another thread during the call.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_set_algorithm(context: *mut c_void, algo: u8) -> i32
+{
+    let context = AssertUnwindSafe(context as *mut ctx::Context);
+    no_unwind!{
+	let context = context.0;
+	c_nonnull!(context, "context");
+	(*context).set_algorithm(c_try!(ctx::Algorithm::from_ffi(algo)));
+	GENERIC_SUCCESS
+    }
+}
+
+/// Set the salt of an opaque context handle
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed, and must not be used from another thread during the call.
+/// If `salt_type` is `KHASH_SALT_TYPE_SPECIFIC`, `bin` must be valid for reads of `sz` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_set_salt(context: *mut c_void, salt_type: u8, bin: *const c_void, sz: size_t) -> i32
+{
+    let context = AssertUnwindSafe(context as *mut ctx::Context);
+    no_unwind!{
+	try error::Error::Unknown;
+	let context = context.0;
+	c_nonnull!(context, "context");
+	(*context).set_salt(c_try!(new_context_salt(salt_type, bin, sz)));
+	GENERIC_SUCCESS
+    }
+}
+
+/// Set the cost parameters of an opaque context handle
+///
+/// # Errors
+/// Costs that cannot be represented in `khash_ctx` flags are rejected (see `khash_new_context_with_cost`), and the handle keeps its previous cost.
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed, and must not be used from another thread during the call.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_set_cost(context: *mut c_void, iterations: u32, memory_kib: u32, parallelism: u32) -> i32
+{
+    let context = AssertUnwindSafe(context as *mut ctx::Context);
+    no_unwind!{
+	let context = context.0;
+	c_nonnull!(context, "context");
+	let cost = ctx::Cost{iterations, memory_kib, parallelism};
+	if !cost.is_ffi_compatible() {
+	    return error_code(error::Error::Parameters(format!("cost {:?} cannot be represented in context flags", cost)));
+	}
+	(*context).set_cost(cost);
+	GENERIC_SUCCESS
+    }
+}
+
+/// Set the output flags (`KHASH_FLAG_*`) of an opaque context handle
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed, and must not be used from another thread during the call.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_set_flags(context: *mut c_void, flags: u16) -> i32
+{
+    let context = AssertUnwindSafe(context as *mut ctx::Context);
+    no_unwind!{
+	try error::Error::Unknown;
+	let context = context.0;
+	c_nonnull!(context, "context");
+	(*context).set_output(c_try!(ctx::OutputOptions::from_flags(u64::from(flags))));
+	GENERIC_SUCCESS
+    }
+}
+
+/// Set the requested digest length of an opaque context handle (`0` for the algorithm's default)
+///
+/// # Errors
+/// Lengths longer than `ctx::MAX_FFI_LENGTH` are rejected, as they are for `khash_ctx`, so `khash_max_length` is a bound for every context.
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed, and must not be used from another thread during the call.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_set_length(context: *mut c_void, length: size_t) -> i32
+{
+    let context = AssertUnwindSafe(context as *mut ctx::Context);
+    no_unwind!{
+	let context = context.0;
+	c_nonnull!(context, "context");
+	if length > ctx::MAX_FFI_LENGTH {
+	    return error_code(error::Error::Parameters(format!("digest length {} is longer than the {} bytes that can be passed through FFI", length, ctx::MAX_FFI_LENGTH)));
+	}
+	(*context).set_length(match length {
+	    0 => None,
+	    length => Some(length),
+	});
+	GENERIC_SUCCESS
+    }
+}
+
+/// Compute a kana hash output with an opaque context handle into a newly allocated `NUL` terminated string.
+///
+/// # Note
+/// The output must be freed with `khash_free_string`.
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed.
+/// `bin` must be valid for reads of `sz` bytes, and `out_str` and `out_len` must each be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_hash(context: *const c_void, bin: *const c_void, sz: size_t, out_str: *mut *mut c_char, out_len: *mut size_t) -> i32
+{
+    let context = AssertUnwindSafe(context as *const ctx::Context);
+    no_unwind!{
+	try error::Error::Unknown;
+	let context = context.0;
+	c_nonnull!(context, "context");
+	hash_to_new_string(&*context, bin, sz, out_str, out_len)
+    }
+}
+
+/// Create a new streaming hash state for an opaque context handle
+///
+/// # Safety
+/// `context` must be `NULL` or a handle from `khash_context_create` or `khash_context_clone` that has not been destroyed.
+/// `stream` must be `NULL` or valid for writes. The state written there must be freed with `khash_stream_free`.
+#[no_mangle]
+pub unsafe extern "C" fn khash_context_stream_new(context: *const c_void, stream: *mut *mut c_void) -> i32
+{
+    let context = AssertUnwindSafe(context as *const ctx::Context);
+    no_unwind!{
+	let context = context.0;
+	c_nonnull!(context, "context");
+	c_nonnull!(stream, "stream");
+	let state: Box<CStream> = Box::new(Some(Hasher::new(&*context)));
+	*stream = Box::into_raw(state) as *mut c_void;
+	GENERIC_SUCCESS
+    }
+}
+
+/// Information about an algorithm compiled into the library, filled by `khash_algorithm_info`
+#[repr(C)]
+struct AlgorithmInfo
+{
+    id: u8,
+    name: *const c_char,
+    digest_size: size_t,
+    cryptographic: bool,
+    extendable: bool,
+}
+
+/// Get the version of the library as a static `NUL` terminated string
+#[no_mangle]
+pub extern "C" fn khash_version() -> *const c_char
+{
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Get the number of algorithms compiled into the library
+#[no_mangle]
+pub extern "C" fn khash_algorithm_count() -> size_t
+{
+    ctx::Algorithm::all().len()
+}
+
+/// The algorithm at `index` of `ctx::Algorithm::all()`, if it is in range
+fn algorithm_at(index: size_t) -> Option<&'static ctx::Algorithm>
+{
+    ctx::Algorithm::all().get(index)
+}
+
+/// Get information about the algorithm at `index` (less than `khash_algorithm_count()`)
+///
+/// # Safety
+/// `info` must be `NULL` or valid for writes of a `khash_algo_info`.
+#[no_mangle]
+pub unsafe extern "C" fn khash_algorithm_info(index: size_t, info: *mut c_void) -> i32
+{
+    let info = info as *mut AlgorithmInfo;
+    no_unwind!{
+	try error::Error::Unknown;
+	c_nonnull!(info, "info");
+	let algo = match algorithm_at(index) {
+	    Some(algo) => algo,
+	    None => return error_code(error::Error::Parameters(format!("algorithm index {} out of range, there are {}", index, ctx::Algorithm::all().len()))),
+	};
+	*info = AlgorithmInfo {
+	    id: c_try!(u8::try_from(algo.clone())),
+	    name: algo.name_with_nul().as_ptr() as *const c_char,
+	    digest_size: algo.digest_size(),
+	    cryptographic: algo.is_cryptographic(),
+	    extendable: algo.is_extendable(),
+	};
+	GENERIC_SUCCESS
+    }
+}
+
+/// Get the `KHASH_ALGO_*` value of the algorithm at `index` (less than `khash_algorithm_count()`), or `KHASH_ALGO_DEFAULT` if `index` is out of range
+#[no_mangle]
+pub extern "C" fn khash_algorithm_id(index: size_t) -> u8
+{
+    algorithm_at(index).and_then(|algo| u8::try_from(algo.clone()).ok()).unwrap_or(ctx::ALGO_DEFAULT)
+}
+
+/// Get the name of the algorithm at `index` as a static `NUL` terminated string, or null if `index` is out of range
+#[no_mangle]
+pub extern "C" fn khash_algorithm_name(index: size_t) -> *const c_char
+{
+    algorithm_at(index).map(|algo| algo.name_with_nul().as_ptr() as *const c_char).unwrap_or(std::ptr::null())
+}
+
+/// Get the digest size in bytes of the algorithm at `index` when no length is requested, or `0` if `index` is out of range
+#[no_mangle]
+pub extern "C" fn khash_algorithm_digest_size(index: size_t) -> size_t
+{
+    algorithm_at(index).map(|algo| algo.digest_size()).unwrap_or(0)
+}
+
+/// Is the algorithm at `index` a cryptographic hash, rather than a checksum (`false` if `index` is out of range)
+#[no_mangle]
+pub extern "C" fn khash_algorithm_cryptographic(index: size_t) -> bool
+{
+    algorithm_at(index).map(|algo| algo.is_cryptographic()).unwrap_or(false)
+}
+
+/// Can the algorithm at `index` produce digests of any requested length (`false` if `index` is out of range)
+#[no_mangle]
+pub extern "C" fn khash_algorithm_extendable(index: size_t) -> bool
+{
+    algorithm_at(index).map(|algo| algo.is_extendable()).unwrap_or(false)
+}
+
+/// Is the algorithm `algo` compiled into the library
+#[no_mangle]
+pub extern "C" fn khash_algorithm_supported(algo: u8) -> bool
+{
+    ctx::Algorithm::from_ffi(algo).is_ok()
+}
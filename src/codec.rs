@@ -0,0 +1,127 @@
+//! Lossless, reversible kana encoding of arbitrary bytes.
+//!
+//! Unlike the mnemonic digests (see `Digest`), the output of `encode` can be turned back into the original bytes with `decode`.
+//! This is useful for human-friendly share codes, short IDs, UUIDs, integers, etc.
+//!
+//! # Alphabet
+//! The symbols used are the kana in `KANA` followed by the small kana in `KANA_SUB`, for an alphabet of 110 symbols.
+//!
+//! # Method
+//! Input is split into blocks of 5 bytes, and each block is encoded as a big-endian integer in 6 base-110 symbols.
+//! A trailing partial block of `n` bytes is encoded in `n + 1` symbols, so the encoding is always unambiguous.
+//! ```
+//! # use khash::codec;
+//! let input = b"Hello world!";
+//! let kana = codec::encode(input);
+//! assert_eq!(&codec::decode(&kana).unwrap()[..], &input[..]);
+//! ```
+
+use crate::*;
+use map::{KANA, KANA_SUB};
+
+/// The number of symbols in the alphabet
+pub const BASE: usize = KANA.len() + KANA_SUB.len();
+
+/// The number of input bytes per full block
+const BLOCK_BYTES: usize = 5;
+/// The number of kana per full block
+const BLOCK_KANA: usize = BLOCK_BYTES + 1;
+
+#[inline] fn symbol(i: usize) -> char
+{
+    if i < KANA.len() {
+	KANA[i]
+    } else {
+	KANA_SUB[i - KANA.len()]
+    }
+}
+
+#[inline] fn index_of(c: char) -> Option<usize>
+{
+    KANA.iter()
+	.chain(KANA_SUB.iter())
+	.position(|&k| k == c)
+}
+
+/// Find the number of kana `encode` will output for an input of `sz` bytes.
+pub const fn encoded_length(sz: usize) -> usize
+{
+    let rem = sz % BLOCK_BYTES;
+    (sz / BLOCK_BYTES) * BLOCK_KANA + if rem > 0 { rem + 1 } else { 0 }
+}
+
+fn encode_block(block: &[u8], output: &mut String)
+{
+    let mut value = 0u64;
+    for &byte in block {
+	value = (value << 8) | u64::from(byte);
+    }
+    let mut kana = [0usize; BLOCK_KANA];
+    let len = block.len() + 1;
+    for digit in kana[..len].iter_mut().rev() {
+	*digit = (value % BASE as u64) as usize;
+	value /= BASE as u64;
+    }
+    output.extend(kana[..len].iter().map(|&i| symbol(i)));
+}
+
+/// Encode a slice of bytes into a kana string.
+pub fn encode<T: AsRef<[u8]>>(bytes: T) -> String
+{
+    let bytes = bytes.as_ref();
+    let mut output = String::with_capacity(encoded_length(bytes.len()) * 3);
+    for block in bytes.chunks(BLOCK_BYTES) {
+	encode_block(block, &mut output);
+    }
+    output
+}
+
+/// Decode a kana string produced by `encode` back into bytes.
+///
+/// # Errors
+/// * `Error::InvalidKana` if the string contains a character that is not in the alphabet, or a block whose value is out of range.
+/// * `Error::Truncated` if the string ends with a partial block too short to hold any bytes.
+pub fn decode<T: AsRef<str>>(string: T) -> Result<Vec<u8>, error::Error>
+{
+    let string = string.as_ref();
+    let mut output = Vec::with_capacity(string.len() / 3);
+    let mut block = [0usize; BLOCK_KANA];
+    let mut len = 0;
+    let mut start = 0;
+
+    fn decode_block(block: &[usize], position: usize, output: &mut Vec<u8>) -> Result<(), error::Error>
+    {
+	let bytes = block.len() - 1;
+	let mut value = 0u64;
+	for &digit in block {
+	    value = value * BASE as u64 + digit as u64;
+	}
+	if value >> (bytes * 8) != 0 {
+	    return Err(error::Error::InvalidKana{position, found: None});
+	}
+	output.extend(value.to_be_bytes()[(8 - bytes)..].iter());
+	Ok(())
+    }
+
+    for (position, c) in string.chars().enumerate()
+    {
+	if len == 0 {
+	    start = position;
+	}
+	block[len] = match index_of(c) {
+	    Some(i) => i,
+	    None => return Err(error::Error::InvalidKana{position, found: Some(c)}),
+	};
+	len += 1;
+	if len == BLOCK_KANA {
+	    decode_block(&block[..], start, &mut output)?;
+	    len = 0;
+	}
+    }
+    match len {
+	0 => (),
+	1 => return Err(error::Error::Truncated),
+	len => decode_block(&block[..len], start, &mut output)?,
+    }
+    Ok(output)
+}
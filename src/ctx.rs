@@ -11,13 +11,35 @@ use std::{
     io::{
 	Read,
     },
+    sync::Arc,
 };
 
 /// An algorithm to use for the context.
 ///
 /// # CRC
-/// `CRC32` and `CRC64` are only available if compiled with the default "crc" feature enabled.
-/// If the library is compiled without this feature, but with the "ffi" feature (i.e. generates native libraries), then FFI requests for the CRC family of digests will instead use the default (`Sha256Truncated`).
+/// The CRC family (`Crc32`, `Crc64`, `Crc32c` and `Crc64Iso`) are only available if compiled with the default "crc" feature enabled.
+/// `Crc32` uses the IEEE polynomial, and `Crc64` is CRC-64/XZ (the reflected ECMA-182 polynomial.)
+/// `Crc32c` uses the SSE4.2 `crc32` instruction when runtime CPU detection allows it, otherwise a software fallback.
+/// If the library is compiled without this feature, but with the "ffi" feature (i.e. generates native libraries), then FFI requests for the CRC family of digests are rejected with `KHASH_ERROR_UNKNOWN_ALGORITHM`.
+///
+/// # xxHash3 and SipHash
+/// Likewise, `Xxh3_64` and `Xxh3_128` require the default "xxh3" feature, and `SipHash13` requires the default "siphash" feature.
+/// The SipHash key is derived from the salt, so different salts give different outputs for the same input.
+///
+/// # Password hashing
+/// `Argon2id`, `Scrypt` and `Pbkdf2` require the default "kdf" feature. They are deliberately slow, for hashing low-entropy input such as PINs or emails.
+/// Their work factor is set by the context's `Cost`.
+/// `Argon2id` reads the whole input into memory, and requires a salt of at least 8 bytes.
+/// (See `hash::KdfState`.)
+///
+/// # Output length
+/// A `Context` can request a specific digest length (see `Context::with_length`).
+/// `Shake256`, `Blake3` and `Blake3Truncated` are extendable output functions, and produce digests of any requested length.
+/// All other algorithms truncate their digest to the requested length, and fail if it is longer than their digest.
+///
+/// # Custom
+/// `Custom` uses a user-supplied `provider::DynProvider`. (See `provider::of` to use a `provider::ByteProvider`.)
+/// Custom algorithms have no FFI representation, so converting one to an FFI algorithm ID (or a context using one to an FFI context) fails.
 #[derive(Clone,Debug,PartialEq,Eq,Hash)]
 pub enum Algorithm
 {
@@ -25,12 +47,54 @@ pub enum Algorithm
     /// The 32 bit CRC checksum (requires default feature `crc`)
     Crc32,
     #[cfg(feature="crc")]
-    /// The 64 bit CRC checksum (requires default feature `crc`)
+    /// The 64 bit CRC checksum, CRC-64/XZ (requires default feature `crc`)
     Crc64,
+    #[cfg(feature="crc")]
+    /// The 32 bit CRC-32C (Castagnoli) checksum, hardware accelerated where available (requires default feature `crc`)
+    Crc32c,
+    #[cfg(feature="crc")]
+    /// The 64 bit CRC-64/GO-ISO checksum (requires default feature `crc`)
+    Crc64Iso,
     /// The SHA256 hash
     Sha256,
     /// The SHA256 hash truncated to the first 64 bits
     Sha256Truncated,
+    /// HMAC-SHA256, keyed with the salt
+    HmacSha256,
+    /// HMAC-SHA256, keyed with the salt, truncated to the first 64 bits
+    HmacSha256Truncated,
+    /// The BLAKE3 hash
+    Blake3,
+    /// The BLAKE3 hash truncated to the first 64 bits
+    Blake3Truncated,
+    /// The SHA512 hash
+    Sha512,
+    /// The SHA-512/256 hash
+    Sha512_256,
+    /// The SHA3-256 hash
+    Sha3_256,
+    #[cfg(feature="xxh3")]
+    /// The 64 bit xxHash3 checksum (requires default feature `xxh3`)
+    Xxh3_64,
+    #[cfg(feature="xxh3")]
+    /// The 128 bit xxHash3 checksum (requires default feature `xxh3`)
+    Xxh3_128,
+    #[cfg(feature="siphash")]
+    /// The 64 bit SipHash-1-3 checksum, keyed with the salt (requires default feature `siphash`)
+    SipHash13,
+    #[cfg(feature="kdf")]
+    /// Argon2id password hashing (requires default feature `kdf`)
+    Argon2id,
+    #[cfg(feature="kdf")]
+    /// scrypt password hashing (requires default feature `kdf`)
+    Scrypt,
+    #[cfg(feature="kdf")]
+    /// PBKDF2-HMAC-SHA256 password hashing (requires default feature `kdf`)
+    Pbkdf2,
+    /// The SHAKE256 extendable output function
+    Shake256,
+    /// A user-supplied digest provider
+    Custom(Arc<dyn provider::DynProvider>),
 }
 
 impl Default for Algorithm
@@ -41,6 +105,277 @@ impl Default for Algorithm
     }
 }
 
+impl Algorithm
+{
+    /// The size in bytes of this algorithm's digest when no length is requested.
+    pub fn digest_size(&self) -> usize
+    {
+	use provider::ByteProvider as P;
+	match self {
+	    #[cfg(feature="crc")] Algorithm::Crc32 => <hash::Crc32Checksum as P>::digest_size(),
+	    #[cfg(feature="crc")] Algorithm::Crc64 => <hash::Crc64Checksum as P>::digest_size(),
+	    #[cfg(feature="crc")] Algorithm::Crc32c => <hash::Crc32cChecksum as P>::digest_size(),
+	    #[cfg(feature="crc")] Algorithm::Crc64Iso => <hash::Crc64IsoChecksum as P>::digest_size(),
+	    Algorithm::Sha256 => <hash::Sha256Hash as P>::digest_size(),
+	    Algorithm::Sha256Truncated => <hash::Sha256Truncated as P>::digest_size(),
+	    Algorithm::HmacSha256 => <hash::HmacSha256 as P>::digest_size(),
+	    Algorithm::HmacSha256Truncated => <hash::HmacSha256Truncated as P>::digest_size(),
+	    Algorithm::Blake3 => <hash::Blake3Hash as P>::digest_size(),
+	    Algorithm::Blake3Truncated => <hash::Blake3Truncated as P>::digest_size(),
+	    Algorithm::Sha512 => <hash::Sha512Hash as P>::digest_size(),
+	    Algorithm::Sha512_256 => <hash::Sha512_256Hash as P>::digest_size(),
+	    Algorithm::Sha3_256 => <hash::Sha3_256Hash as P>::digest_size(),
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_64 => <hash::Xxh3_64Checksum as P>::digest_size(),
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_128 => <hash::Xxh3_128Checksum as P>::digest_size(),
+	    #[cfg(feature="siphash")] Algorithm::SipHash13 => <hash::SipHash13Checksum as P>::digest_size(),
+	    #[cfg(feature="kdf")] Algorithm::Argon2id => hash::KDF_SIZE,
+	    #[cfg(feature="kdf")] Algorithm::Scrypt => hash::KDF_SIZE,
+	    #[cfg(feature="kdf")] Algorithm::Pbkdf2 => hash::KDF_SIZE,
+	    Algorithm::Shake256 => <hash::Shake256Hash as P>::digest_size(),
+	    Algorithm::Custom(provider) => provider.digest_size(),
+	}
+    }
+
+    /// Can this algorithm produce a digest of any length
+    pub fn is_extendable(&self) -> bool
+    {
+	match self {
+	    Algorithm::Shake256 | Algorithm::Blake3 | Algorithm::Blake3Truncated => true,
+	    _ => false,
+	}
+    }
+
+    /// Is this algorithm a cryptographic hash (or password hash.)
+    ///
+    /// The checksums are not, and neither are `Custom` algorithms since they are unknown.
+    pub fn is_cryptographic(&self) -> bool
+    {
+	match self {
+	    #[cfg(feature="crc")] Algorithm::Crc32 | Algorithm::Crc64 | Algorithm::Crc32c | Algorithm::Crc64Iso => false,
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_64 | Algorithm::Xxh3_128 => false,
+	    #[cfg(feature="siphash")] Algorithm::SipHash13 => false,
+	    Algorithm::Custom(_) => false,
+	    _ => true,
+	}
+    }
+
+    /// The name of this algorithm
+    pub fn name(&self) -> &'static str
+    {
+	let name = self.name_with_nul();
+	&name[..name.len() - 1]
+    }
+
+    /// The name of this algorithm, `NUL` terminated for FFI
+    pub(crate) fn name_with_nul(&self) -> &'static str
+    {
+	match self {
+	    #[cfg(feature="crc")] Algorithm::Crc32 => "CRC32\0",
+	    #[cfg(feature="crc")] Algorithm::Crc64 => "CRC-64/XZ\0",
+	    #[cfg(feature="crc")] Algorithm::Crc32c => "CRC-32C\0",
+	    #[cfg(feature="crc")] Algorithm::Crc64Iso => "CRC-64/GO-ISO\0",
+	    Algorithm::Sha256 => "SHA256\0",
+	    Algorithm::Sha256Truncated => "SHA256 (truncated)\0",
+	    Algorithm::HmacSha256 => "HMAC-SHA256\0",
+	    Algorithm::HmacSha256Truncated => "HMAC-SHA256 (truncated)\0",
+	    Algorithm::Blake3 => "BLAKE3\0",
+	    Algorithm::Blake3Truncated => "BLAKE3 (truncated)\0",
+	    Algorithm::Sha512 => "SHA512\0",
+	    Algorithm::Sha512_256 => "SHA-512/256\0",
+	    Algorithm::Sha3_256 => "SHA3-256\0",
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_64 => "XXH3-64\0",
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_128 => "XXH3-128\0",
+	    #[cfg(feature="siphash")] Algorithm::SipHash13 => "SipHash-1-3\0",
+	    #[cfg(feature="kdf")] Algorithm::Argon2id => "Argon2id\0",
+	    #[cfg(feature="kdf")] Algorithm::Scrypt => "scrypt\0",
+	    #[cfg(feature="kdf")] Algorithm::Pbkdf2 => "PBKDF2-HMAC-SHA256\0",
+	    Algorithm::Shake256 => "SHAKE256\0",
+	    Algorithm::Custom(_) => "Custom\0",
+	}
+    }
+
+    /// All the (non-custom) algorithms compiled into the library, in order of their FFI IDs.
+    pub fn all() -> &'static [Algorithm]
+    {
+	const ALL: &[Algorithm] = &[
+	    #[cfg(feature="crc")] Algorithm::Crc32,
+	    #[cfg(feature="crc")] Algorithm::Crc64,
+	    Algorithm::Sha256,
+	    Algorithm::Sha256Truncated,
+	    Algorithm::HmacSha256,
+	    Algorithm::HmacSha256Truncated,
+	    Algorithm::Blake3,
+	    Algorithm::Blake3Truncated,
+	    Algorithm::Sha512,
+	    Algorithm::Sha512_256,
+	    Algorithm::Sha3_256,
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_64,
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_128,
+	    #[cfg(feature="siphash")] Algorithm::SipHash13,
+	    #[cfg(feature="crc")] Algorithm::Crc32c,
+	    #[cfg(feature="crc")] Algorithm::Crc64Iso,
+	    #[cfg(feature="kdf")] Algorithm::Argon2id,
+	    #[cfg(feature="kdf")] Algorithm::Scrypt,
+	    #[cfg(feature="kdf")] Algorithm::Pbkdf2,
+	    Algorithm::Shake256,
+	];
+	ALL
+    }
+}
+
+/// Input to be fed into a digest by `Context::compute`.
+///
+/// Implemented for any `&mut` stream, and for whole slices wrapped in `Slice`.
+pub(crate) trait Input
+{
+    fn provide<P: provider::ByteProvider>(self, salt: &salt::Salt, output: &mut usize) -> Result<P, error::Error>;
+    #[cfg(feature="kdf")]
+    fn provide_with_cost<P: hash::CostProvider>(self, salt: &salt::Salt, cost: &Cost, output: &mut usize) -> Result<P, error::Error>;
+    fn provide_xof<P: provider::XofProvider>(self, salt: &salt::Salt, length: usize, output: &mut usize) -> Result<Box<[u8]>, error::Error>;
+    fn provide_dyn(self, provider: &dyn provider::DynProvider, salt: &salt::Salt, output: &mut usize) -> Result<Box<[u8]>, error::Error>;
+}
+
+impl<'a, R: Read + ?Sized> Input for &'a mut R
+{
+    #[inline] fn provide<P: provider::ByteProvider>(self, salt: &salt::Salt, output: &mut usize) -> Result<P, error::Error>
+    {
+	P::compute(self, salt, output)
+    }
+    #[cfg(feature="kdf")]
+    fn provide_with_cost<P: hash::CostProvider>(self, salt: &salt::Salt, cost: &Cost, output: &mut usize) -> Result<P, error::Error>
+    {
+	let mut state = hash::KdfState::<P>::new(salt, cost);
+	*output = provider::update_from(self, |bytes| state.update(bytes))?;
+	state.finish()
+    }
+    #[inline] fn provide_xof<P: provider::XofProvider>(self, salt: &salt::Salt, length: usize, output: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	P::compute_xof(self, salt, length, output)
+    }
+    #[inline] fn provide_dyn(mut self, provider: &dyn provider::DynProvider, salt: &salt::Salt, output: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	provider.compute_dyn(&mut self, salt, output)
+    }
+}
+
+/// A whole slice of input, so providers can use `ByteProvider::compute_slice`.
+pub(crate) struct Slice<'a>(pub &'a [u8]);
+
+impl<'a> Input for Slice<'a>
+{
+    #[inline] fn provide<P: provider::ByteProvider>(self, salt: &salt::Salt, output: &mut usize) -> Result<P, error::Error>
+    {
+	P::compute_slice(self.0, salt, output)
+    }
+    #[cfg(feature="kdf")]
+    #[inline] fn provide_with_cost<P: hash::CostProvider>(self, salt: &salt::Salt, cost: &Cost, output: &mut usize) -> Result<P, error::Error>
+    {
+	*output = self.0.len();
+	P::compute_with_cost(self.0, salt, cost)
+    }
+    #[inline] fn provide_xof<P: provider::XofProvider>(self, salt: &salt::Salt, length: usize, output: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	P::compute_xof_slice(self.0, salt, length, output)
+    }
+    #[inline] fn provide_dyn(self, provider: &dyn provider::DynProvider, salt: &salt::Salt, output: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	provider.compute_dyn_slice(self.0, salt, output)
+    }
+}
+
+/// Cost parameters for the password-hashing algorithms.
+///
+/// A value of `0` means the algorithm's default.
+/// * `Argon2id` uses all three.
+/// * `Scrypt` uses `memory_kib` (which must be a power of 2) and `parallelism`.
+/// * `Pbkdf2` uses `iterations`.
+/// The other algorithms ignore the cost.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash,Default)]
+pub struct Cost
+{
+    /// The number of iterations (passes)
+    pub iterations: u32,
+    /// The memory to use in KiB
+    pub memory_kib: u32,
+    /// The number of threads (lanes)
+    pub parallelism: u32,
+}
+
+/// The kana script a hash is written in.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum Script
+{
+    /// Both hiragana and katakana (the default.)
+    Mixed,
+    /// Only hiragana. Katakana are written as the equivalent hiragana.
+    Hiragana,
+    /// Only katakana. Hiragana are written as the equivalent katakana.
+    Katakana,
+}
+
+impl Default for Script
+{
+    #[inline] fn default() -> Self
+    {
+	Self::Mixed
+    }
+}
+
+impl Script
+{
+    /// Write `kana` in this script.
+    pub fn apply(self, kana: char) -> char
+    {
+	// The hiragana and katakana blocks are laid out in the same order
+	const OFFSET: u32 = 0x60;
+	match (self, u32::from(kana)) {
+	    (Script::Hiragana, code @ 0x30a1..=0x30f6) => std::char::from_u32(code - OFFSET).unwrap_or(kana),
+	    (Script::Katakana, code @ 0x3041..=0x3096) => std::char::from_u32(code + OFFSET).unwrap_or(kana),
+	    _ => kana,
+	}
+    }
+}
+
+/// Options for how the kana of a hash are written.
+///
+/// Hashes written with a single script cannot be compared with ones written with the default mixed script.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct OutputOptions
+{
+    /// The script to write the kana in
+    pub script: Script,
+    /// Write `OutputOptions::SEPARATOR` between each group of kana (each 2 bytes of digest.)
+    pub separators: bool,
+    /// Write a `NUL` terminator after the hash when it is copied into a buffer through FFI. (Strings allocated by the library are always terminated.)
+    pub nul_terminator: bool,
+}
+
+impl Default for OutputOptions
+{
+    #[inline] fn default() -> Self
+    {
+	Self {
+	    script: Default::default(),
+	    separators: false,
+	    nul_terminator: true,
+	}
+    }
+}
+
+impl OutputOptions
+{
+    /// The separator written between groups of kana
+    pub const SEPARATOR: char = '-';
+
+    /// The length in bytes of the kana output for a digest of `digest_size` bytes with these options (not including a `NUL` terminator.)
+    pub fn kana_length(&self, digest_size: usize) -> usize
+    {
+	let groups = digest_size.div_ceil(2);
+	let separators = if self.separators { groups.saturating_sub(1) } else { 0 };
+	kana_length(digest_size) + separators * Self::SEPARATOR.len_utf8()
+    }
+}
+
 /// A kana-hash context containing it's salt and algorithm.
 ///
 /// # Default
@@ -50,19 +385,47 @@ pub struct Context
 {
     algo: Algorithm,
     salt: salt::Salt,
+    cost: Cost,
+    length: Option<usize>,
+    output: OutputOptions,
 }
 
 impl Context
 {
     /// Create a new kana-hash context with an algorithm and a salt
     pub fn new(algo: Algorithm, salt: impl Into<salt::Salt>) -> Self
+    {
+	Self::new_with_cost(algo, salt, Default::default())
+    }
+
+    /// Create a new kana-hash context with an algorithm, a salt, and cost parameters for the password-hashing algorithms
+    pub fn new_with_cost(algo: Algorithm, salt: impl Into<salt::Salt>, cost: Cost) -> Self
     {
 	Self {
 	    algo,
 	    salt: salt.into(),
+	    cost,
+	    length: None,
+	    output: Default::default(),
 	}
     }
 
+    /// Request a specific digest length in bytes.
+    ///
+    /// Extendable output algorithms produce exactly this many bytes, others are truncated to it. (See `Algorithm`.)
+    pub fn with_length(mut self, length: usize) -> Self
+    {
+	self.length = Some(length);
+	self
+    }
+
+    /// Set how the kana of the hash are written.
+    pub fn with_output(mut self, output: OutputOptions) -> Self
+    {
+	self.output = output;
+	self
+    }
+
     /// The algorithm used
     pub fn get_algorithm(&self) -> &Algorithm
     {
@@ -73,59 +436,231 @@ impl Context
     {
 	&self.salt
     }
+    /// The cost parameters used
+    pub fn get_cost(&self) -> &Cost
+    {
+	&self.cost
+    }
+    /// The requested digest length, if any
+    pub fn get_length(&self) -> Option<usize>
+    {
+	self.length
+    }
+    /// How the kana of the hash are written
+    pub fn get_output(&self) -> &OutputOptions
+    {
+	&self.output
+    }
+
+    /// Replace the algorithm used
+    pub(crate) fn set_algorithm(&mut self, algo: Algorithm)
+    {
+	self.algo = algo;
+    }
+    /// Replace the salt used
+    pub(crate) fn set_salt(&mut self, salt: salt::Salt)
+    {
+	self.salt = salt;
+    }
+    /// Replace the cost parameters used
+    pub(crate) fn set_cost(&mut self, cost: Cost)
+    {
+	self.cost = cost;
+    }
+    /// Replace the requested digest length
+    pub(crate) fn set_length(&mut self, length: Option<usize>)
+    {
+	self.length = length;
+    }
+    /// Replace the output options
+    pub(crate) fn set_output(&mut self, output: OutputOptions)
+    {
+	self.output = output;
+    }
+
+    /// The size in bytes of the digest this context produces.
+    pub fn digest_size(&self) -> usize
+    {
+	self.length.unwrap_or_else(|| self.algo.digest_size())
+    }
+
+    /// The length in bytes of the kana this context produces (not including a `NUL` terminator.)
+    pub fn kana_length(&self) -> usize
+    {
+	self.output.kana_length(self.digest_size())
+    }
     
-    pub(crate) fn compute<R: Read>(&self, mut from: R) -> Result<(usize, Box<[u8]>), error::Error>
+    pub(crate) fn compute<I: Input>(&self, from: I) -> Result<(usize, Box<[u8]>), error::Error>
     {
-	fn provide<P,R>(input: &mut R, salt: &salt::Salt, output: &mut usize) -> Result<Vec<u8>, error::Error>
+	fn provide<P,I>(input: I, salt: &salt::Salt, output: &mut usize) -> Result<Vec<u8>, error::Error>
 	where P: provider::ByteProvider,
-	      R: Read + ?Sized
+	      I: Input
 	{
-	    let this = P::compute(input, &salt, output)?;
+	    let this = input.provide::<P>(&salt, output)?;
 	    Ok(Vec::from(this.bytes()))
 	}
+	#[cfg(feature="kdf")]
+	fn provide_with_cost<P,I>(input: I, salt: &salt::Salt, cost: &Cost, output: &mut usize) -> Result<Vec<u8>, error::Error>
+	where P: hash::CostProvider,
+	      I: Input
+	{
+	    let this = input.provide_with_cost::<P>(&salt, cost, output)?;
+	    Ok(Vec::from(this.bytes()))
+	}
+	fn provide_xof<P,I>(input: I, salt: &salt::Salt, length: usize, output: &mut usize) -> Result<Vec<u8>, error::Error>
+	where P: provider::XofProvider,
+	      I: Input
+	{
+	    Ok(input.provide_xof::<P>(&salt, length, output)?.into_vec())
+	}
+
+	let length = self.checked_digest_size()?;
 
 	let mut output = 0usize;
-	let bytes = match self.algo
+	let bytes = match &self.algo
 	{
-	    #[cfg(feature="crc")] Algorithm::Crc32 => provide::<hash::Crc32Checksum, _>(&mut from, &self.salt, &mut output)?,
-	    #[cfg(feature="crc")] Algorithm::Crc64 => provide::<hash::Crc64Checksum, _>(&mut from, &self.salt, &mut output)?,
-	    Algorithm::Sha256 => provide::<hash::Sha256Hash, _>(&mut from, &self.salt, &mut output)?,
-	    Algorithm::Sha256Truncated => provide::<hash::Sha256Truncated, _>(&mut from, &self.salt, &mut output)?,
-	}.into_boxed_slice();
+	    Algorithm::Custom(provider) => from.provide_dyn(&**provider, &self.salt, &mut output)?.into_vec(),
+	    Algorithm::Shake256 => provide_xof::<hash::Shake256Hash, _>(from, &self.salt, length, &mut output)?,
+	    Algorithm::Blake3 | Algorithm::Blake3Truncated if self.length.is_some() => provide_xof::<hash::Blake3Hash, _>(from, &self.salt, length, &mut output)?,
+	    #[cfg(feature="crc")] Algorithm::Crc32 => provide::<hash::Crc32Checksum, _>(from, &self.salt, &mut output)?,
+	    #[cfg(feature="crc")] Algorithm::Crc64 => provide::<hash::Crc64Checksum, _>(from, &self.salt, &mut output)?,
+	    #[cfg(feature="crc")] Algorithm::Crc32c => provide::<hash::Crc32cChecksum, _>(from, &self.salt, &mut output)?,
+	    #[cfg(feature="crc")] Algorithm::Crc64Iso => provide::<hash::Crc64IsoChecksum, _>(from, &self.salt, &mut output)?,
+	    Algorithm::Sha256 => provide::<hash::Sha256Hash, _>(from, &self.salt, &mut output)?,
+	    Algorithm::Sha256Truncated => provide::<hash::Sha256Truncated, _>(from, &self.salt, &mut output)?,
+	    Algorithm::HmacSha256 => provide::<hash::HmacSha256, _>(from, &self.salt, &mut output)?,
+	    Algorithm::HmacSha256Truncated => provide::<hash::HmacSha256Truncated, _>(from, &self.salt, &mut output)?,
+	    Algorithm::Blake3 => provide::<hash::Blake3Hash, _>(from, &self.salt, &mut output)?,
+	    Algorithm::Blake3Truncated => provide::<hash::Blake3Truncated, _>(from, &self.salt, &mut output)?,
+	    Algorithm::Sha512 => provide::<hash::Sha512Hash, _>(from, &self.salt, &mut output)?,
+	    Algorithm::Sha512_256 => provide::<hash::Sha512_256Hash, _>(from, &self.salt, &mut output)?,
+	    Algorithm::Sha3_256 => provide::<hash::Sha3_256Hash, _>(from, &self.salt, &mut output)?,
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_64 => provide::<hash::Xxh3_64Checksum, _>(from, &self.salt, &mut output)?,
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_128 => provide::<hash::Xxh3_128Checksum, _>(from, &self.salt, &mut output)?,
+	    #[cfg(feature="siphash")] Algorithm::SipHash13 => provide::<hash::SipHash13Checksum, _>(from, &self.salt, &mut output)?,
+	    #[cfg(feature="kdf")] Algorithm::Argon2id => provide_with_cost::<hash::Argon2idHash, _>(from, &self.salt, &self.cost, &mut output)?,
+	    #[cfg(feature="kdf")] Algorithm::Scrypt => provide_with_cost::<hash::ScryptHash, _>(from, &self.salt, &self.cost, &mut output)?,
+	    #[cfg(feature="kdf")] Algorithm::Pbkdf2 => provide_with_cost::<hash::Pbkdf2Hash, _>(from, &self.salt, &self.cost, &mut output)?,
+	};
+	Ok((output, self.truncate(bytes)?))
+    }
 
-	Ok((output, bytes))
+    /// Start an incremental digest with this context.
+    ///
+    /// The digest bytes it produces still need to be `truncate`d.
+    pub(crate) fn begin(&self) -> Box<dyn provider::DynState>
+    {
+	fn state<P>(salt: &salt::Salt) -> Box<dyn provider::DynState>
+	where P: provider::ByteProvider + 'static,
+	      P::State: Send
+	{
+	    Box::new(provider::Running::<P>::new(salt))
+	}
+	fn xof_state<P>(salt: &salt::Salt, length: usize) -> Box<dyn provider::DynState>
+	where P: provider::XofProvider + 'static,
+	      P::State: Send
+	{
+	    Box::new(provider::RunningXof::<P>::new(salt, length))
+	}
+
+	match &self.algo
+	{
+	    Algorithm::Custom(provider) => provider.begin(&self.salt),
+	    Algorithm::Shake256 => xof_state::<hash::Shake256Hash>(&self.salt, self.digest_size()),
+	    Algorithm::Blake3 | Algorithm::Blake3Truncated if self.length.is_some() => xof_state::<hash::Blake3Hash>(&self.salt, self.digest_size()),
+	    #[cfg(feature="crc")] Algorithm::Crc32 => state::<hash::Crc32Checksum>(&self.salt),
+	    #[cfg(feature="crc")] Algorithm::Crc64 => state::<hash::Crc64Checksum>(&self.salt),
+	    #[cfg(feature="crc")] Algorithm::Crc32c => state::<hash::Crc32cChecksum>(&self.salt),
+	    #[cfg(feature="crc")] Algorithm::Crc64Iso => state::<hash::Crc64IsoChecksum>(&self.salt),
+	    Algorithm::Sha256 => state::<hash::Sha256Hash>(&self.salt),
+	    Algorithm::Sha256Truncated => state::<hash::Sha256Truncated>(&self.salt),
+	    Algorithm::HmacSha256 => state::<hash::HmacSha256>(&self.salt),
+	    Algorithm::HmacSha256Truncated => state::<hash::HmacSha256Truncated>(&self.salt),
+	    Algorithm::Blake3 => state::<hash::Blake3Hash>(&self.salt),
+	    Algorithm::Blake3Truncated => state::<hash::Blake3Truncated>(&self.salt),
+	    Algorithm::Sha512 => state::<hash::Sha512Hash>(&self.salt),
+	    Algorithm::Sha512_256 => state::<hash::Sha512_256Hash>(&self.salt),
+	    Algorithm::Sha3_256 => state::<hash::Sha3_256Hash>(&self.salt),
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_64 => state::<hash::Xxh3_64Checksum>(&self.salt),
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_128 => state::<hash::Xxh3_128Checksum>(&self.salt),
+	    #[cfg(feature="siphash")] Algorithm::SipHash13 => state::<hash::SipHash13Checksum>(&self.salt),
+	    #[cfg(feature="kdf")] Algorithm::Argon2id => Box::new(hash::KdfState::<hash::Argon2idHash>::new(&self.salt, &self.cost)),
+	    #[cfg(feature="kdf")] Algorithm::Scrypt => Box::new(hash::KdfState::<hash::ScryptHash>::new(&self.salt, &self.cost)),
+	    #[cfg(feature="kdf")] Algorithm::Pbkdf2 => Box::new(hash::KdfState::<hash::Pbkdf2Hash>::new(&self.salt, &self.cost)),
+	}
+    }
+
+    /// The size of the digest this context produces, or an error if the requested length is invalid.
+    pub(crate) fn checked_digest_size(&self) -> Result<usize, error::Error>
+    {
+	if self.length == Some(0) {
+	    return Err(error::Error::Parameters("digest length cannot be 0".to_owned()));
+	}
+	Ok(self.digest_size())
     }
 
+    /// Truncate a full digest to the size this context produces.
+    pub(crate) fn truncate(&self, mut bytes: Vec<u8>) -> Result<Box<[u8]>, error::Error>
+    {
+	let length = self.checked_digest_size()?;
+	if length > bytes.len() {
+	    return Err(error::Error::Parameters(format!("digest length {} is longer than the {} byte digest", length, bytes.len())));
+	}
+	bytes.truncate(length);
+	Ok(bytes.into_boxed_slice())
+    }
+
+    /// Convert into an FFI context.
+    ///
+    /// # Errors
+    /// `Custom` algorithms, digest lengths longer than `MAX_FFI_LENGTH`, and costs that do not fit in the flags, cannot be passed through FFI.
     #[cfg(feature="ffi")] 
-    pub(crate) unsafe fn into_raw(self) -> CContext
+    pub(crate) unsafe fn into_raw(self) -> Result<CContext, error::Error>
     {
-	CContext{ 
-	    algo: u8::from(self.algo),
+	Ok(CContext{ 
+	    algo: u8::try_from(self.algo)?,
+	    flags: self.cost.into_flags()? | length_into_flags(self.length)? | self.output.into_flags(),
 	    salt: salt::into_raw(self.salt),
-	    flags: Default::default(),
-	}
+	})
     }
     
     #[cfg(feature="ffi")] 
-    pub(crate) unsafe fn clone_from_raw(from: *const CContext) -> Self
+    pub(crate) unsafe fn clone_from_raw(from: *const CContext) -> Result<Self, error::Error>
     {
-	let from = &*from;
-	Self {
-	    algo: from.algo.into(),
-	    salt: salt::clone_from_raw(&from.salt as *const salt::FFI),
+	if from.is_null() {
+	    return Err(error::Error::NullPointer("context"));
 	}
+	let from = &*from;
+	Ok(Self {
+	    algo: Algorithm::from_ffi(from.algo)?,
+	    output: OutputOptions::from_flags(from.flags)?,
+	    salt: salt::clone_from_raw(&from.salt as *const salt::FFI)?,
+	    cost: Cost::from_flags(from.flags),
+	    length: length_from_flags(from.flags),
+	})
     }
     
+    /// Consume an FFI context.
+    ///
+    /// A context with an invalid salt is not consumed, but one with only an invalid algorithm or flags is.
     #[cfg(feature="ffi")] 
-    pub(crate) unsafe fn from_raw(from: *mut CContext) -> Self
+    pub(crate) unsafe fn from_raw(from: *mut CContext) -> Result<Self, error::Error>
     {
+	if from.is_null() {
+	    return Err(error::Error::NullPointer("context"));
+	}
 	let from = &mut *from;
-	let output = Self{
-	    algo: from.algo.into(),
-	    salt: salt::from_raw(&mut from.salt as *mut salt::FFI),
-	};
+	let algo = Algorithm::from_ffi(from.algo);
+	let output = OutputOptions::from_flags(from.flags);
+	let salt = salt::from_raw(&mut from.salt as *mut salt::FFI)?;
 	from.algo = 0;
-	output
+	Ok(Self{
+	    algo: algo?,
+	    output: output?,
+	    salt,
+	    cost: Cost::from_flags(from.flags),
+	    length: length_from_flags(from.flags),
+	})
     }
 }
 
@@ -136,6 +671,9 @@ impl Default for Context
 	Self {
 	    algo: Default::default(),
 	    salt: Default::default(),
+	    cost: Default::default(),
+	    length: None,
+	    output: Default::default(),
 	}
     }
 }
@@ -145,6 +683,138 @@ pub(crate) const ALGO_CRC32: u8 = 1;
 pub(crate) const ALGO_CRC64: u8 = 2;
 pub(crate) const ALGO_SHA256: u8 = 3;
 pub(crate) const ALGO_SHA256_TRUNCATED: u8 = 4;
+pub(crate) const ALGO_HMAC_SHA256: u8 = 5;
+pub(crate) const ALGO_HMAC_SHA256_TRUNCATED: u8 = 6;
+pub(crate) const ALGO_BLAKE3: u8 = 7;
+pub(crate) const ALGO_BLAKE3_TRUNCATED: u8 = 8;
+pub(crate) const ALGO_SHA512: u8 = 9;
+pub(crate) const ALGO_SHA512_256: u8 = 10;
+pub(crate) const ALGO_SHA3_256: u8 = 11;
+pub(crate) const ALGO_XXH3_64: u8 = 12;
+pub(crate) const ALGO_XXH3_128: u8 = 13;
+pub(crate) const ALGO_SIPHASH13: u8 = 14;
+pub(crate) const ALGO_CRC32C: u8 = 15;
+pub(crate) const ALGO_CRC64_ISO: u8 = 16;
+pub(crate) const ALGO_ARGON2ID: u8 = 17;
+pub(crate) const ALGO_SCRYPT: u8 = 18;
+pub(crate) const ALGO_PBKDF2: u8 = 19;
+pub(crate) const ALGO_SHAKE256: u8 = 20;
+
+// Bits 16-31 of `CContext.flags` hold the requested digest length, `0` for none.
+pub(crate) const LENGTH_SHIFT: u32 = 16;
+pub(crate) const LENGTH_MASK: u64 = 0xffff;
+
+/// The longest digest length that can be requested through FFI context flags.
+pub(crate) const MAX_FFI_LENGTH: usize = LENGTH_MASK as usize;
+
+fn length_from_flags(flags: u64) -> Option<usize>
+{
+    match (flags >> LENGTH_SHIFT) & LENGTH_MASK {
+	0 => None,
+	length => Some(length as usize),
+    }
+}
+
+/// # Errors
+/// Lengths longer than `MAX_FFI_LENGTH` do not fit in the flags.
+fn length_into_flags(length: Option<usize>) -> Result<u64, error::Error>
+{
+    match length {
+	Some(length) if length > MAX_FFI_LENGTH => Err(error::Error::Parameters(format!("digest length {} is longer than the {} bytes that can be passed through FFI", length, MAX_FFI_LENGTH))),
+	length => Ok((length.unwrap_or(0) as u64) << LENGTH_SHIFT),
+    }
+}
+
+// Bits 0-15 of `CContext.flags` hold the `OutputOptions`.
+pub(crate) const FLAG_HIRAGANA: u64 = 1 << 0;
+pub(crate) const FLAG_KATAKANA: u64 = 1 << 1;
+pub(crate) const FLAG_SEPARATORS: u64 = 1 << 2;
+pub(crate) const FLAG_NO_NUL_TERMINATOR: u64 = 1 << 3;
+pub(crate) const OUTPUT_FLAGS_MASK: u64 = 0xffff;
+const OUTPUT_FLAGS_KNOWN: u64 = FLAG_HIRAGANA | FLAG_KATAKANA | FLAG_SEPARATORS | FLAG_NO_NUL_TERMINATOR;
+
+impl OutputOptions
+{
+    /// Decode output options from the lower 16 bits of FFI context flags.
+    ///
+    /// # Errors
+    /// `Error::Parameters` for unknown flags, or both `FLAG_HIRAGANA` and `FLAG_KATAKANA`.
+    pub(crate) fn from_flags(flags: u64) -> Result<Self, error::Error>
+    {
+	let flags = flags & OUTPUT_FLAGS_MASK;
+	if flags & !OUTPUT_FLAGS_KNOWN != 0 {
+	    return Err(error::Error::Parameters(format!("unknown output flags {:#x}", flags & !OUTPUT_FLAGS_KNOWN)));
+	}
+	Ok(Self {
+	    script: match (flags & FLAG_HIRAGANA != 0, flags & FLAG_KATAKANA != 0) {
+		(false, false) => Script::Mixed,
+		(true, false) => Script::Hiragana,
+		(false, true) => Script::Katakana,
+		(true, true) => return Err(error::Error::Parameters("output cannot be both hiragana and katakana only".to_owned())),
+	    },
+	    separators: flags & FLAG_SEPARATORS != 0,
+	    nul_terminator: flags & FLAG_NO_NUL_TERMINATOR == 0,
+	})
+    }
+
+    /// Encode these options into the lower 16 bits of FFI context flags.
+    pub(crate) fn into_flags(self) -> u64
+    {
+	(match self.script {
+	    Script::Mixed => 0,
+	    Script::Hiragana => FLAG_HIRAGANA,
+	    Script::Katakana => FLAG_KATAKANA,
+	}) | if self.separators { FLAG_SEPARATORS } else { 0 }
+	    | if self.nul_terminator { 0 } else { FLAG_NO_NUL_TERMINATOR }
+    }
+}
+
+// The upper 32 bits of `CContext.flags` hold the cost parameters.
+pub(crate) const COST_ITERATIONS_SHIFT: u32 = 32;
+pub(crate) const COST_ITERATIONS_MASK: u64 = (1 << 21) - 1;
+pub(crate) const COST_MEMORY_SHIFT: u32 = 53;
+pub(crate) const COST_MEMORY_MASK: u64 = (1 << 6) - 1;
+pub(crate) const COST_PARALLELISM_SHIFT: u32 = 59;
+pub(crate) const COST_PARALLELISM_MASK: u64 = (1 << 5) - 1;
+
+impl Cost
+{
+    /// Decode cost parameters from FFI context flags.
+    ///
+    /// The memory cost is stored as its base 2 logarithm, with `0` meaning the default.
+    pub(crate) fn from_flags(flags: u64) -> Self
+    {
+	let memory = (flags >> COST_MEMORY_SHIFT) & COST_MEMORY_MASK;
+	Self {
+	    iterations: ((flags >> COST_ITERATIONS_SHIFT) & COST_ITERATIONS_MASK) as u32,
+	    memory_kib: if memory == 0 || memory > 31 { 0 } else { 1 << memory },
+	    parallelism: ((flags >> COST_PARALLELISM_SHIFT) & COST_PARALLELISM_MASK) as u32,
+	}
+    }
+
+    /// Check these parameters can be represented in FFI context flags.
+    pub(crate) fn is_ffi_compatible(&self) -> bool
+    {
+	u64::from(self.iterations) <= COST_ITERATIONS_MASK
+	    && (self.memory_kib == 0 || (self.memory_kib.is_power_of_two() && self.memory_kib > 1))
+	    && u64::from(self.parallelism) <= COST_PARALLELISM_MASK
+    }
+
+    /// Encode these parameters into FFI context flags.
+    ///
+    /// # Errors
+    /// Parameters that cannot be represented (see `is_ffi_compatible`) are rejected instead of truncated.
+    pub(crate) fn into_flags(self) -> Result<u64, error::Error>
+    {
+	if !self.is_ffi_compatible() {
+	    return Err(error::Error::Parameters(format!("cost {:?} cannot be represented in context flags", self)));
+	}
+	let memory = if self.memory_kib > 1 { 31 - self.memory_kib.leading_zeros() } else { 0 };
+	Ok((u64::from(self.iterations) << COST_ITERATIONS_SHIFT)
+	    | (u64::from(memory) << COST_MEMORY_SHIFT)
+	    | (u64::from(self.parallelism) << COST_PARALLELISM_SHIFT))
+    }
+}
 
 /// FFI context
 #[derive(Debug)]
@@ -152,23 +822,60 @@ pub(crate) const ALGO_SHA256_TRUNCATED: u8 = 4;
 pub(crate) struct CContext
 {
     algo: u8,
-    flags: u64, //nothing yet, might be flags later idk
+    flags: u64, //upper 32 bits are the `Cost`, bits 16-31 are the digest length, bits 0-15 are the `OutputOptions`
     salt: salt::FFI,
     
 }
 
-impl From<Algorithm> for u8
+impl TryFrom<Algorithm> for u8
 {
-    fn from(al: Algorithm) -> Self
+    type Error = error::Error;
+
+    /// The FFI algorithm ID of `al`.
+    ///
+    /// # Errors
+    /// `Custom` algorithms have no ID.
+    fn try_from(al: Algorithm) -> Result<Self, Self::Error>
     {
-	match al {
+	Ok(match al {
 	    #[cfg(feature="crc")] Algorithm::Crc32 => ALGO_CRC32,
 	    #[cfg(feature="crc")] Algorithm::Crc64 => ALGO_CRC64,
+	    #[cfg(feature="crc")] Algorithm::Crc32c => ALGO_CRC32C,
+	    #[cfg(feature="crc")] Algorithm::Crc64Iso => ALGO_CRC64_ISO,
+	    #[cfg(feature="kdf")] Algorithm::Argon2id => ALGO_ARGON2ID,
+	    #[cfg(feature="kdf")] Algorithm::Scrypt => ALGO_SCRYPT,
+	    #[cfg(feature="kdf")] Algorithm::Pbkdf2 => ALGO_PBKDF2,
+	    Algorithm::Shake256 => ALGO_SHAKE256,
+	    Algorithm::Custom(_) => return Err(error::Error::Parameters("custom algorithms have no FFI representation".to_owned())),
 	    Algorithm::Sha256 => ALGO_SHA256,
 	    Algorithm::Sha256Truncated => ALGO_SHA256_TRUNCATED,
+	    Algorithm::HmacSha256 => ALGO_HMAC_SHA256,
+	    Algorithm::HmacSha256Truncated => ALGO_HMAC_SHA256_TRUNCATED,
+	    Algorithm::Blake3 => ALGO_BLAKE3,
+	    Algorithm::Blake3Truncated => ALGO_BLAKE3_TRUNCATED,
+	    Algorithm::Sha512 => ALGO_SHA512,
+	    Algorithm::Sha512_256 => ALGO_SHA512_256,
+	    Algorithm::Sha3_256 => ALGO_SHA3_256,
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_64 => ALGO_XXH3_64,
+	    #[cfg(feature="xxh3")] Algorithm::Xxh3_128 => ALGO_XXH3_128,
+	    #[cfg(feature="siphash")] Algorithm::SipHash13 => ALGO_SIPHASH13,
+	})
+    }
+}
+impl Algorithm
+{
+    /// Convert an algorithm ID passed through FFI, rejecting IDs that are unknown or not compiled in instead of using the default.
+    #[cfg(any(feature="ffi", feature="wasm", feature="jni"))]
+    pub(crate) fn from_ffi(al: u8) -> Result<Self, error::Error>
+    {
+	match (al, Self::from(al)) {
+	    (ALGO_DEFAULT, algo) => Ok(algo),
+	    (al, algo) if u8::try_from(algo.clone()).ok() == Some(al) => Ok(algo),
+	    (al, _) => Err(error::Error::UnknownAlgorithm(al)),
 	}
     }
 }
+
 impl From<u8> for Algorithm
 {
     fn from(al: u8) -> Self
@@ -176,8 +883,24 @@ impl From<u8> for Algorithm
 	match al {
 	    #[cfg(feature="crc")] ALGO_CRC32 => Algorithm::Crc32,
 	    #[cfg(feature="crc")] ALGO_CRC64 => Algorithm::Crc64,
+	    #[cfg(feature="crc")] ALGO_CRC32C => Algorithm::Crc32c,
+	    #[cfg(feature="crc")] ALGO_CRC64_ISO => Algorithm::Crc64Iso,
+	    #[cfg(feature="kdf")] ALGO_ARGON2ID => Algorithm::Argon2id,
+	    #[cfg(feature="kdf")] ALGO_SCRYPT => Algorithm::Scrypt,
+	    #[cfg(feature="kdf")] ALGO_PBKDF2 => Algorithm::Pbkdf2,
+	    ALGO_SHAKE256 => Algorithm::Shake256,
 	    ALGO_SHA256 => Algorithm::Sha256,
 	    ALGO_SHA256_TRUNCATED => Algorithm::Sha256Truncated,
+	    ALGO_HMAC_SHA256 => Algorithm::HmacSha256,
+	    ALGO_HMAC_SHA256_TRUNCATED => Algorithm::HmacSha256Truncated,
+	    ALGO_BLAKE3 => Algorithm::Blake3,
+	    ALGO_BLAKE3_TRUNCATED => Algorithm::Blake3Truncated,
+	    ALGO_SHA512 => Algorithm::Sha512,
+	    ALGO_SHA512_256 => Algorithm::Sha512_256,
+	    ALGO_SHA3_256 => Algorithm::Sha3_256,
+	    #[cfg(feature="xxh3")] ALGO_XXH3_64 => Algorithm::Xxh3_64,
+	    #[cfg(feature="xxh3")] ALGO_XXH3_128 => Algorithm::Xxh3_128,
+	    #[cfg(feature="siphash")] ALGO_SIPHASH13 => Algorithm::SipHash13,
 	    _ => Self::default(),
 	}
     }
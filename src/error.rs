@@ -18,6 +18,28 @@ pub enum Error
     Length{expected: usize, got:usize,},
     /// The random number generator failed.
     RNG(getrandom::Error),
+    /// A string being decoded contained invalid kana at this character position.
+    ///
+    /// `found` is `None` if the kana were all valid, but the block starting at `position` was out of range.
+    InvalidKana{position: usize, found: Option<char>,},
+    /// A string being decoded ended part way through a block.
+    Truncated,
+    /// The algorithm's parameters (e.g. cost or salt length) were invalid.
+    Parameters(String),
+    /// An output buffer of `got` bytes was too small to hold the `required` bytes.
+    BufferTooSmall{required: usize, got: usize,},
+    /// A required pointer argument was null.
+    NullPointer(&'static str),
+    /// An input buffer was empty.
+    EmptyInput,
+    /// An algorithm ID passed through FFI was unknown, or the algorithm was not compiled in.
+    UnknownAlgorithm(u8),
+    /// A salt type ID passed through FFI was unknown.
+    UnknownSaltType(u8),
+    /// A salt passed through FFI was `size` bytes, more than the `max` allowed.
+    SaltTooLarge{size: usize, max: usize,},
+    /// A specific salt passed through FFI was empty.
+    EmptySalt,
     /// There was an unknown error.
     Unknown,
 }
@@ -45,6 +67,17 @@ impl fmt::Display for Error
 	    Error::Format(fmt) => write!(f, "fmt: {}", fmt),
 	    Error::Length{expected, got} => write!(f, "invalid length: expected {}, got {}", expected, got),
 	    Error::RNG(rng) => write!(f, "rng error: {}", rng),
+	    Error::InvalidKana{position, found: Some(found)} => write!(f, "invalid kana '{}' at position {}", found, position),
+	    Error::InvalidKana{position, found: None} => write!(f, "invalid kana block at position {}", position),
+	    Error::Truncated => write!(f, "input was truncated"),
+	    Error::Parameters(msg) => write!(f, "invalid parameters: {}", msg),
+	    Error::BufferTooSmall{required, got} => write!(f, "buffer too small: {} bytes required, got {}", required, got),
+	    Error::NullPointer(name) => write!(f, "`{}` was null", name),
+	    Error::EmptyInput => write!(f, "input was empty"),
+	    Error::UnknownAlgorithm(algo) => write!(f, "unknown algorithm {}", algo),
+	    Error::UnknownSaltType(salt_type) => write!(f, "unknown salt type {}", salt_type),
+	    Error::SaltTooLarge{size, max} => write!(f, "salt too large: {} bytes, max is {}", size, max),
+	    Error::EmptySalt => write!(f, "specific salt was empty"),
 	    _ => write!(f, "unknown failure"),
 	}
     }
@@ -59,6 +92,16 @@ impl From<Error> for i32
 	    Error::Format(_) => 2,
 	    Error::Length{..} => 3,
 	    Error::RNG(_) => 4,
+	    Error::InvalidKana{..} => 5,
+	    Error::Truncated => 6,
+	    Error::Parameters(_) => 7,
+	    Error::BufferTooSmall{..} => 8,
+	    Error::NullPointer(_) => 9,
+	    Error::EmptyInput => 10,
+	    Error::UnknownAlgorithm(_) => 11,
+	    Error::UnknownSaltType(_) => 12,
+	    Error::SaltTooLarge{..} => 13,
+	    Error::EmptySalt => 14,
 	    _ => -1,
 	}
     }
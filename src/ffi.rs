@@ -1,13 +1,62 @@
 #![allow(unused_macros)]
+use std::{
+    cell::RefCell,
+    any::Any,
+};
 
 pub const GENERIC_ERROR: i32 = -1;
 pub const GENERIC_SUCCESS: i32 = 0;
 
+thread_local! {
+    /// The message of the last error returned through FFI on this thread.
+    static LAST_ERROR: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Set the last error message for this thread.
+pub fn set_last_error(message: impl Into<String>)
+{
+    let message = message.into();
+    LAST_ERROR.with(move |last| *last.borrow_mut() = Some(message));
+}
+
+/// Get the last error message for this thread, if there is one.
+pub fn last_error() -> Option<String>
+{
+    LAST_ERROR.with(|last| last.borrow().clone())
+}
+
+/// Record an error as the last error for this thread and return its FFI error code.
+pub fn error_code(error: crate::error::Error) -> i32
+{
+    set_last_error(error.to_string());
+    i32::from(error)
+}
+
+/// Record a generic failure as the last error for this thread and return `GENERIC_ERROR`.
+pub fn generic_error(message: impl Into<String>) -> i32
+{
+    set_last_error(message);
+    GENERIC_ERROR
+}
+
+/// Record a caught panic as the last error for this thread.
+pub fn set_panic_error(payload: Box<dyn Any + Send>)
+{
+    let message = if let Some(message) = payload.downcast_ref::<&'static str>() {
+	message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+	&message[..]
+    } else {
+	"unknown panic payload"
+    };
+    set_last_error(format!("kana-hash panic: {}", message));
+}
+
 macro_rules! c_try {
     ($e:expr) => {
 	match $e {
 	    Ok(v) => v,
-	    Err(e) => return i32::from(e),
+	    Err(e) => return $crate::ffi::error_code(e),
 	}
     }
 }
@@ -19,7 +68,10 @@ macro_rules! no_unwind {
 		$($e)*
 	    }) {
 		Ok(v) => i32::from(v),
-		Err(_) => return i32::from($t),
+		Err(payload) => {
+		    $crate::ffi::set_panic_error(payload);
+		    return i32::from($t);
+		},
 	    }
 	}
     };
@@ -30,17 +82,25 @@ macro_rules! no_unwind {
 }
 
 
+macro_rules! c_nonnull {
+    ($ptr:expr, $name:literal) => {
+	if $ptr.is_null() {
+	    return $crate::ffi::error_code($crate::error::Error::NullPointer($name));
+	}
+    }
+}
+
 macro_rules! string_from_ffi {
     ($file:expr) => {
 	unsafe {
 	    let file = $file;
 	    if file.is_null() {
-		return $crate::ffi::GENERIC_ERROR;
+		return $crate::ffi::error_code($crate::error::Error::NullPointer("string"));
 	    }
 	    let file = CStr::from_ptr(file);
 	    match file.to_str() {
 		Ok(file) => file.to_owned(),
-		Err(_) => return $crate::ffi::GENERIC_ERROR,
+		Err(e) => return $crate::ffi::generic_error(format!("string was not valid UTF-8: {}", e)),
 	    }
 	}
     }
@@ -0,0 +1,248 @@
+use super::*;
+use blake3::Hasher;
+use std::{
+    io::{
+	self,
+	Read,
+    },
+};
+use provider::Incremental;
+
+pub const BLAKE3_SIZE: usize = blake3::OUT_LEN;
+pub const BLAKE3_TRUNCATE: usize = 8;
+
+/// Inputs at least this large are hashed across threads (when compiled with feature `threads`.)
+pub const BLAKE3_THREAD_THRESHOLD: usize = 128 * 1024;
+
+/// The size of the buffer used when reading from a stream to hash across threads.
+#[cfg(feature="threads")]
+const BLAKE3_STREAM_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[repr(C)]
+#[repr(packed)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Blake3Hash
+{
+    hash: [u8; BLAKE3_SIZE],
+}
+
+/// Feed `bytes` into `hasher`, across threads if they are large enough.
+#[inline] pub(crate) fn blake3_update(hasher: &mut Hasher, bytes: &[u8])
+{
+    #[cfg(feature="threads")]
+    if bytes.len() >= BLAKE3_THREAD_THRESHOLD {
+	hasher.update_rayon(bytes);
+	return;
+    }
+    hasher.update(bytes);
+}
+
+/// Fill as much of `buffer` as possible from `input`, returning the number of bytes read.
+#[cfg(feature="threads")]
+fn fill<T: Read + ?Sized>(input: &mut T, buffer: &mut [u8]) -> io::Result<usize>
+{
+    let mut done = 0;
+    while done < buffer.len() {
+	match input.read(&mut buffer[done..]) {
+	    Ok(0) => break,
+	    Ok(read) => done += read,
+	    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+	    Err(e) => return Err(e),
+	}
+    }
+    Ok(done)
+}
+
+/// The running state of a `Blake3Hash` (or `Blake3Truncated`.)
+pub struct Blake3State
+{
+    hasher: Hasher,
+    salt: salt::Salt,
+}
+
+impl Blake3State
+{
+    /// Append the salt and finish the hash.
+    fn output(mut self) -> blake3::OutputReader
+    {
+	self.hasher.update(self.salt.bytes());
+	self.hasher.finalize_xof()
+    }
+
+    /// Feed all of a stream into the hash, reading large enough blocks to hash across threads.
+    #[cfg(feature="threads")]
+    fn update_from<T: Read + ?Sized>(&mut self, input: &mut T) -> io::Result<usize>
+    {
+	let mut buffer = vec![0u8; BLAKE3_STREAM_BUFFER_SIZE];
+	let mut read;
+	let mut done=0;
+	while (read = fill(input, &mut buffer[..])?, read!=0).1
+	{
+	    blake3_update(&mut self.hasher, &buffer[..read]);
+	    done+=read;
+	}
+	Ok(done)
+    }
+
+    /// Feed all of a stream into the hash.
+    #[cfg(not(feature="threads"))]
+    fn update_from<T: Read + ?Sized>(&mut self, input: &mut T) -> io::Result<usize>
+    {
+	provider::update_from(input, |bytes| { self.hasher.update(bytes); })
+    }
+
+    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, done: &mut usize) -> io::Result<Self>
+    {
+	let mut state = Self::new(salt);
+	*done = state.update_from(input)?;
+	Ok(state)
+    }
+}
+
+impl Incremental for Blake3State
+{
+    type Output = Blake3Hash;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self {
+	    hasher: Hasher::new(),
+	    salt: salt.clone(),
+	}
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	blake3_update(&mut self.hasher, bytes)
+    }
+
+    fn finish(self) -> Result<Blake3Hash, error::Error>
+    {
+	let mut hash = [0u8; BLAKE3_SIZE];
+	self.output().fill(&mut hash[..]);
+	Ok(Blake3Hash{hash})
+    }
+}
+
+impl Blake3Hash
+{
+    pub fn bytes(&self) -> &[u8; BLAKE3_SIZE]
+    {
+	&self.hash
+    }
+}
+
+use std::fmt;
+impl fmt::Display for Blake3Hash
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Blake3hash (")?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
+
+impl provider::ByteProvider for Blake3Hash
+{
+    type State = Blake3State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.bytes()[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	BLAKE3_SIZE
+    }
+
+    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, done: &mut usize) -> Result<Self, error::Error>
+    {
+	Blake3State::compute(input, salt, done)?.finish()
+    }
+}
+
+impl provider::XofProvider for Blake3Hash
+{
+    fn finish_xof(state: Blake3State, length: usize) -> Result<Box<[u8]>, error::Error>
+    {
+	let mut output = vec![0u8; length].into_boxed_slice();
+	state.output().fill(&mut output[..]);
+	Ok(output)
+    }
+
+    fn compute_xof<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, length: usize, done: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	Self::finish_xof(Blake3State::compute(input, salt, done)?, length)
+    }
+}
+
+#[repr(C)]
+#[repr(packed)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Blake3Truncated
+{
+    hash: [u8; BLAKE3_TRUNCATE],
+}
+
+/// The running state of a `Blake3Truncated`.
+pub struct Blake3TruncatedState(Blake3State);
+
+impl Incremental for Blake3TruncatedState
+{
+    type Output = Blake3Truncated;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Blake3State::new(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+
+    fn finish(self) -> Result<Blake3Truncated, error::Error>
+    {
+	let mut hash = [0u8; BLAKE3_TRUNCATE];
+	self.0.output().fill(&mut hash[..]);
+	Ok(Blake3Truncated{hash})
+    }
+}
+
+impl fmt::Display for Blake3Truncated
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Blake3Truncated[->{}] (", BLAKE3_TRUNCATE)?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
+
+impl provider::ByteProvider for Blake3Truncated
+{
+    type State = Blake3TruncatedState;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	BLAKE3_TRUNCATE
+    }
+
+    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, done: &mut usize) -> Result<Self, error::Error>
+    {
+	Blake3TruncatedState(Blake3State::compute(input, salt, done)?).finish()
+    }
+}
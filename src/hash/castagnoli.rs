@@ -0,0 +1,68 @@
+use super::*;
+use provider::Incremental;
+
+/// CRC-32C (Castagnoli.)
+///
+/// Uses the SSE4.2 (or ARMv8 CRC) instructions when runtime CPU detection finds them, otherwise falls back to a software implementation.
+pub struct Crc32cChecksum
+{
+    hash: u32,
+}
+
+/// The running state of a `Crc32cChecksum`.
+pub struct Crc32cState
+{
+    hash: u32,
+    salt: salt::Salt,
+}
+
+impl Incremental for Crc32cState
+{
+    type Output = Crc32cChecksum;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self {
+	    hash: 0,
+	    salt: salt.clone(),
+	}
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.hash = crc32c::crc32c_append(self.hash, bytes);
+    }
+
+    fn finish(self) -> Result<Crc32cChecksum, error::Error>
+    {
+	Ok(Crc32cChecksum{hash: crc32c::crc32c_append(self.hash, self.salt.bytes())})
+    }
+}
+
+impl provider::ByteProvider for Crc32cChecksum
+{
+    type State = Crc32cState;
+
+    fn bytes(&self) -> &[u8]
+    {
+	unsafe{reinterpret::bytes(&self.hash)}
+    }
+
+    fn digest_size() -> usize
+    {
+	std::mem::size_of::<u32>()
+    }
+}
+
+use std::fmt;
+impl fmt::Display for Crc32cChecksum
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Crc32cchecksum (")?;
+	for b in provider::ByteProvider::bytes(self) {
+	    write!(f, "{:02x}", *b)?;
+	}
+	write!(f, ")")
+    }
+}
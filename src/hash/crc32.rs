@@ -1,30 +1,55 @@
 use super::*;
 use crc::{Hasher32, crc32};
+use provider::Incremental;
 
 pub struct Crc32Checksum
 {
     hash: u32,
 }
 
+/// The running state of a `Crc32Checksum`.
+pub struct Crc32State
+{
+    hasher: crc32::Digest,
+    salt: salt::Salt,
+}
+
+impl Incremental for Crc32State
+{
+    type Output = Crc32Checksum;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self {
+	    hasher: crc32::Digest::new(crc32::IEEE),
+	    salt: salt.clone(),
+	}
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.hasher.write(bytes)
+    }
+
+    fn finish(mut self) -> Result<Crc32Checksum, error::Error>
+    {
+	self.hasher.write(self.salt.bytes());
+	Ok(Crc32Checksum{hash: self.hasher.sum32()})
+    }
+}
+
 impl provider::ByteProvider for Crc32Checksum
 {
+    type State = Crc32State;
+
     fn bytes(&self) -> &[u8]
     {
 	unsafe{reinterpret::bytes(&self.hash)}
     }
-    
-    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, done: &mut usize) -> Result<Self, error::Error>
+
+    fn digest_size() -> usize
     {
-	let mut buffer = [0u8; BUFFER_SIZE];
-	let mut hasher = crc32::Digest::new(crc32::IEEE);
-	let mut read;
-	while (read = input.read(&mut buffer[..])?, read!=0).1
-	{
-	    hasher.write(&buffer[..read]);
-	    *done += read;
-	}
-	hasher.write(salt.bytes());
-	Ok(Self{hash: hasher.sum32()})
+	std::mem::size_of::<u32>()
     }
 }
 
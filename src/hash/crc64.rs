@@ -1,30 +1,116 @@
 use super::*;
 use crc::{Hasher64, crc64};
+use provider::Incremental;
 
+/// CRC-64/XZ (the reflected ECMA-182 polynomial.)
 pub struct Crc64Checksum
 {
     hash: u64,
 }
 
+/// CRC-64/GO-ISO (the reflected ISO 3309 polynomial.)
+pub struct Crc64IsoChecksum
+{
+    hash: u64,
+}
+
+/// A running CRC-64, with the salt to append to its input.
+struct Running
+{
+    hasher: crc64::Digest,
+    salt: salt::Salt,
+}
+
+impl Running
+{
+    fn new(salt: &salt::Salt, poly: u64) -> Self
+    {
+	Self {
+	    hasher: crc64::Digest::new(poly),
+	    salt: salt.clone(),
+	}
+    }
+
+    fn sum(mut self) -> u64
+    {
+	self.hasher.write(self.salt.bytes());
+	self.hasher.sum64()
+    }
+}
+
+/// The running state of a `Crc64Checksum`.
+pub struct Crc64State(Running);
+
+impl Incremental for Crc64State
+{
+    type Output = Crc64Checksum;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Running::new(salt, crc64::ECMA))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.hasher.write(bytes)
+    }
+
+    fn finish(self) -> Result<Crc64Checksum, error::Error>
+    {
+	Ok(Crc64Checksum{hash: self.0.sum()})
+    }
+}
+
+/// The running state of a `Crc64IsoChecksum`.
+pub struct Crc64IsoState(Running);
+
+impl Incremental for Crc64IsoState
+{
+    type Output = Crc64IsoChecksum;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Running::new(salt, crc64::ISO))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.hasher.write(bytes)
+    }
+
+    fn finish(self) -> Result<Crc64IsoChecksum, error::Error>
+    {
+	Ok(Crc64IsoChecksum{hash: self.0.sum()})
+    }
+}
+
 impl provider::ByteProvider for Crc64Checksum
 {
+    type State = Crc64State;
+
     fn bytes(&self) -> &[u8]
     {
 	unsafe{reinterpret::bytes(&self.hash)}
     }
-    
-    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, done: &mut usize) -> Result<Self, error::Error>
+
+    fn digest_size() -> usize
     {
-	let mut buffer = [0u8; BUFFER_SIZE];
-	let mut hasher = crc64::Digest::new(crc64::ECMA);
-	let mut read;
-	while (read = input.read(&mut buffer[..])?, read!=0).1
-	{
-	    hasher.write(&buffer[..read]);
-	    *done += read;
-	}
-	hasher.write(salt.bytes());
-	Ok(Self{hash: hasher.sum64()})
+	std::mem::size_of::<u64>()
+    }
+}
+
+impl provider::ByteProvider for Crc64IsoChecksum
+{
+    type State = Crc64IsoState;
+
+    fn bytes(&self) -> &[u8]
+    {
+	unsafe{reinterpret::bytes(&self.hash)}
+    }
+
+    fn digest_size() -> usize
+    {
+	std::mem::size_of::<u64>()
     }
 }
 
@@ -40,3 +126,15 @@ impl fmt::Display for Crc64Checksum
 	write!(f, ")")
     }
 }
+
+impl fmt::Display for Crc64IsoChecksum
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Crc64Isochecksum (")?;
+	for b in provider::ByteProvider::bytes(self) {
+	    write!(f, "{:02x}", *b)?;
+	}
+	write!(f, ")")
+    }
+}
@@ -0,0 +1,155 @@
+use super::*;
+use sha2::Sha256;
+use hmac::{
+    Hmac,
+    Mac,
+    NewMac,
+};
+
+use provider::Incremental;
+
+type HmacSha256Digest = Hmac<Sha256>;
+
+pub const HMAC_SHA256_SIZE: usize = SHA256_SIZE;
+pub const HMAC_SHA256_TRUNCATE: usize = SHA256_TRUNCATE;
+
+#[repr(C)]
+#[repr(packed)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct HmacSha256
+{
+    hash: [u8; HMAC_SHA256_SIZE],
+}
+
+/// Start a MAC keyed with the salt.
+fn keyed(salt: &salt::Salt) -> HmacSha256Digest
+{
+    match HmacSha256Digest::new_from_slice(salt.bytes()) {
+	Ok(hmac) => hmac,
+	// HMAC accepts keys of any length
+	Err(_) => unreachable!(),
+    }
+}
+
+/// The running state of a `HmacSha256`, keyed with the salt.
+pub struct HmacSha256State(HmacSha256Digest);
+
+impl Incremental for HmacSha256State
+{
+    type Output = HmacSha256;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(keyed(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+
+    fn finish(self) -> Result<HmacSha256, error::Error>
+    {
+	let mut hash = [0u8; HMAC_SHA256_SIZE];
+	array::copy_slice(&mut hash, self.0.finalize().into_bytes());
+	Ok(HmacSha256{hash})
+    }
+}
+
+impl HmacSha256
+{
+    pub fn bytes(&self) -> &[u8; HMAC_SHA256_SIZE]
+    {
+	&self.hash
+    }
+}
+
+use std::fmt;
+impl fmt::Display for HmacSha256
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "HmacSha256 (")?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
+
+impl provider::ByteProvider for HmacSha256
+{
+    type State = HmacSha256State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.bytes()[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	HMAC_SHA256_SIZE
+    }
+}
+
+#[repr(C)]
+#[repr(packed)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct HmacSha256Truncated
+{
+    hash: [u8; HMAC_SHA256_TRUNCATE],
+}
+
+impl fmt::Display for HmacSha256Truncated
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "HmacSha256Truncated[->{}] (", HMAC_SHA256_TRUNCATE)?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
+
+/// The running state of a `HmacSha256Truncated`.
+pub struct HmacSha256TruncatedState(HmacSha256Digest);
+
+impl Incremental for HmacSha256TruncatedState
+{
+    type Output = HmacSha256Truncated;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(keyed(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+
+    fn finish(self) -> Result<HmacSha256Truncated, error::Error>
+    {
+	let mut hash = [0u8; HMAC_SHA256_TRUNCATE];
+	array::copy_slice(&mut hash, self.0.finalize().into_bytes());
+	Ok(HmacSha256Truncated{hash})
+    }
+}
+
+impl provider::ByteProvider for HmacSha256Truncated
+{
+    type State = HmacSha256TruncatedState;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	HMAC_SHA256_TRUNCATE
+    }
+}
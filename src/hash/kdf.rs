@@ -0,0 +1,235 @@
+use super::*;
+use ctx::Cost;
+use sha2::{Sha256, Digest};
+use hmac::Hmac;
+use std::{
+    fmt,
+    marker::PhantomData,
+};
+
+/// The output size of the password-hardening algorithms.
+pub const KDF_SIZE: usize = 32;
+
+/// The default Argon2id iteration count.
+pub const ARGON2_DEFAULT_ITERATIONS: u32 = 2;
+/// The default Argon2id memory cost in KiB.
+pub const ARGON2_DEFAULT_MEMORY_KIB: u32 = 19 * 1024;
+/// The default Argon2id lane count.
+pub const ARGON2_DEFAULT_PARALLELISM: u32 = 1;
+
+/// The default scrypt memory cost in KiB. (`N` = 2^15 with `r` = 8.)
+pub const SCRYPT_DEFAULT_MEMORY_KIB: u32 = 32 * 1024;
+/// The default scrypt parallelism.
+pub const SCRYPT_DEFAULT_PARALLELISM: u32 = 1;
+/// The scrypt block size parameter `r`.
+const SCRYPT_R: u32 = 8;
+
+/// The default PBKDF2-HMAC-SHA256 iteration count.
+pub const PBKDF2_DEFAULT_ITERATIONS: u32 = 600_000;
+
+/// The block size of SHA256. Longer HMAC-SHA256 keys are replaced by their SHA256 hash.
+const HMAC_SHA256_BLOCK_SIZE: usize = 64;
+
+/// A provider whose work factor is set by a `ctx::Cost`.
+///
+/// These need their whole input at once (see `KdfState`.)
+/// They are not `provider::ByteProvider`s, since they cannot be computed without a cost.
+pub trait CostProvider: Sized + fmt::Display
+{
+    /// Is the input only used as an HMAC-SHA256 key
+    const HMAC_KEY: bool = false;
+
+    fn compute_with_cost(input: &[u8], salt: &salt::Salt, cost: &Cost) -> Result<Self, error::Error>;
+    /// The bytes of the digest
+    fn bytes(&self) -> &[u8];
+}
+
+/// The input fed into a `KdfState` so far.
+enum Password
+{
+    Buffered(Vec<u8>),
+    /// The input is longer than a SHA256 block, and only used as an HMAC-SHA256 key.
+    Hashed(Sha256),
+}
+
+/// The running state of a password hash.
+///
+/// Argon2id hashes the length of its input before the input itself, so its input is buffered until `finish`.
+/// PBKDF2 and scrypt only use their input as an HMAC-SHA256 key, which HMAC replaces with its SHA256 hash when it is longer than a block, so at most one block of their input is buffered.
+pub struct KdfState<P>
+{
+    input: Password,
+    salt: salt::Salt,
+    cost: Cost,
+    _provider: PhantomData<fn() -> P>,
+}
+
+impl<P: CostProvider> KdfState<P>
+{
+    /// Start a password hash with this salt and cost
+    pub fn new(salt: &salt::Salt, cost: &Cost) -> Self
+    {
+	Self {
+	    input: Password::Buffered(Vec::new()),
+	    salt: salt.clone(),
+	    cost: *cost,
+	    _provider: PhantomData,
+	}
+    }
+
+    /// Feed more input into the hash
+    pub fn update(&mut self, bytes: &[u8])
+    {
+	match &mut self.input {
+	    Password::Buffered(buffer) if P::HMAC_KEY && buffer.len() + bytes.len() > HMAC_SHA256_BLOCK_SIZE => {
+		let mut digest = Sha256::new();
+		digest.update(&buffer[..]);
+		digest.update(bytes);
+		self.input = Password::Hashed(digest);
+	    },
+	    Password::Buffered(buffer) => buffer.extend_from_slice(bytes),
+	    Password::Hashed(digest) => digest.update(bytes),
+	}
+    }
+
+    /// Finish the hash
+    pub fn finish(self) -> Result<P, error::Error>
+    {
+	match self.input {
+	    Password::Buffered(input) => P::compute_with_cost(&input[..], &self.salt, &self.cost),
+	    Password::Hashed(digest) => P::compute_with_cost(&digest.finalize()[..], &self.salt, &self.cost),
+	}
+    }
+}
+
+impl<P: CostProvider> provider::DynState for KdfState<P>
+{
+    fn update(&mut self, bytes: &[u8])
+    {
+	KdfState::update(self, bytes)
+    }
+
+    fn finish(self: Box<Self>) -> Result<Box<[u8]>, error::Error>
+    {
+	Ok(Box::from(KdfState::finish(*self)?.bytes()))
+    }
+}
+
+#[inline] fn or_default(value: u32, default: u32) -> u32
+{
+    if value == 0 {
+	default
+    } else {
+	value
+    }
+}
+
+/// Argon2id
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Argon2idHash
+{
+    hash: [u8; KDF_SIZE],
+}
+
+impl CostProvider for Argon2idHash
+{
+    fn compute_with_cost(input: &[u8], salt: &salt::Salt, cost: &Cost) -> Result<Self, error::Error>
+    {
+	use argon2::{Argon2, Params, Version};
+	let params = Params::new(or_default(cost.memory_kib, ARGON2_DEFAULT_MEMORY_KIB),
+				 or_default(cost.iterations, ARGON2_DEFAULT_ITERATIONS),
+				 or_default(cost.parallelism, ARGON2_DEFAULT_PARALLELISM),
+				 Some(KDF_SIZE)).map_err(|e| error::Error::Parameters(format!("argon2: {}", e)))?;
+	let mut hash = [0u8; KDF_SIZE];
+	Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params)
+	    .hash_password_into(input, salt.bytes(), &mut hash[..])
+	    .map_err(|e| error::Error::Parameters(format!("argon2: {}", e)))?;
+	Ok(Self{hash})
+    }
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+}
+
+/// scrypt
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct ScryptHash
+{
+    hash: [u8; KDF_SIZE],
+}
+
+impl CostProvider for ScryptHash
+{
+    const HMAC_KEY: bool = true;
+
+    fn compute_with_cost(input: &[u8], salt: &salt::Salt, cost: &Cost) -> Result<Self, error::Error>
+    {
+	// Memory used is 128 * r * N bytes, so with r = 8, N is the memory cost in KiB.
+	let memory = or_default(cost.memory_kib, SCRYPT_DEFAULT_MEMORY_KIB);
+	if !memory.is_power_of_two() {
+	    return Err(error::Error::Parameters(format!("scrypt: memory cost {}KiB is not a power of 2", memory)));
+	}
+	let params = scrypt::Params::new(memory.trailing_zeros() as u8,
+					       SCRYPT_R,
+					       or_default(cost.parallelism, SCRYPT_DEFAULT_PARALLELISM))
+	    .map_err(|e| error::Error::Parameters(format!("scrypt: {}", e)))?;
+	let mut hash = [0u8; KDF_SIZE];
+	scrypt::scrypt(input, salt.bytes(), &params, &mut hash[..])
+	    .map_err(|e| error::Error::Parameters(format!("scrypt: {}", e)))?;
+	Ok(Self{hash})
+    }
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+}
+
+/// PBKDF2-HMAC-SHA256
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Pbkdf2Hash
+{
+    hash: [u8; KDF_SIZE],
+}
+
+impl CostProvider for Pbkdf2Hash
+{
+    const HMAC_KEY: bool = true;
+
+    fn compute_with_cost(input: &[u8], salt: &salt::Salt, cost: &Cost) -> Result<Self, error::Error>
+    {
+	let mut hash = [0u8; KDF_SIZE];
+	pbkdf2::pbkdf2::<Hmac<Sha256>>(input, salt.bytes(), or_default(cost.iterations, PBKDF2_DEFAULT_ITERATIONS), &mut hash[..]);
+	Ok(Self{hash})
+    }
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+}
+
+macro_rules! kdf_display {
+    ($($name:ident => $display:literal),*) => {
+	$(
+	    impl fmt::Display for $name
+	    {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+		{
+		    write!(f, concat!($display, " ("))?;
+		    for byte in self.hash.iter()
+		    {
+			write!(f, "{:02x}", *byte)?;
+		    }
+		    write!(f, ")")
+		}
+	    }
+	)*
+    };
+}
+
+kdf_display!(Argon2idHash => "Argon2idhash",
+	      ScryptHash => "Scrypthash",
+	      Pbkdf2Hash => "Pbkdf2hash");
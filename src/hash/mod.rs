@@ -13,5 +13,40 @@ mod crc32;
 #[cfg(feature="crc")] 
 pub use crc32::*;
 
+#[cfg(feature="crc")] 
+mod castagnoli;
+#[cfg(feature="crc")] 
+pub use castagnoli::*;
+
 mod sha256t;
 pub use sha256t::*;
+
+mod hmac_sha256;
+pub use hmac_sha256::*;
+
+mod blake3_hash;
+pub use blake3_hash::*;
+
+mod sha512;
+pub use sha512::*;
+
+mod sha3_256;
+pub use sha3_256::*;
+
+#[cfg(feature="xxh3")] 
+mod xxh3;
+#[cfg(feature="xxh3")] 
+pub use self::xxh3::*;
+
+#[cfg(feature="siphash")] 
+mod siphash;
+#[cfg(feature="siphash")] 
+pub use siphash::*;
+
+#[cfg(feature="kdf")] 
+mod kdf;
+#[cfg(feature="kdf")] 
+pub use kdf::*;
+
+mod shake256;
+pub use shake256::*;
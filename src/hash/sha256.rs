@@ -6,6 +6,7 @@ use std::{
 	Read,
     },
 };
+use provider::Incremental;
 
 pub const SHA256_SIZE: usize = 32;
 #[repr(C)]
@@ -17,34 +18,77 @@ pub struct Sha256Hash
 }
 
 
-fn compute_stream<T: Read +?Sized, D: Digest>(input: &mut T, output: &mut D) -> io::Result<usize>
+/// A running `Digest`, with the salt to append to its input.
+pub(crate) struct Salted<D>
 {
-    let mut buffer = [0u8; BUFFER_SIZE];
+    hasher: D,
+    salt: salt::Salt,
+}
+
+impl<D: Digest> Salted<D>
+{
+    pub(crate) fn new(salt: &salt::Salt) -> Self
+    {
+	Self {
+	    hasher: D::new(),
+	    salt: salt.clone(),
+	}
+    }
+
+    #[inline] pub(crate) fn update(&mut self, bytes: &[u8])
+    {
+	self.hasher.update(bytes)
+    }
 
-    let mut read;
-    let mut done=0;
-    while (read = input.read(&mut buffer[..])?, read!=0).1
+    /// Append the salt, and copy as much of the digest as fits into `hash`.
+    pub(crate) fn finish_into(mut self, hash: &mut [u8])
     {
-	output.update(&buffer[..read]);
-	done+=read;
+	self.hasher.update(self.salt.bytes());
+	array::copy_slice(hash, self.hasher.finalize());
     }
-    Ok(done)
 }
 
-impl Sha256Hash
+/// The running state of a `Sha256Hash`.
+pub struct Sha256State(Salted<Sha256>);
+
+impl Sha256State
 {
-    /// Compute a hash from a stream.
-    pub fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt) -> io::Result<(usize, Self)>
+    fn hash(self) -> Sha256Hash
     {
 	let mut hash = [0u8; SHA256_SIZE];
+	self.0.finish_into(&mut hash[..]);
+	Sha256Hash{hash}
+    }
+}
+
+impl Incremental for Sha256State
+{
+    type Output = Sha256Hash;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Salted::new(salt))
+    }
 
-	let mut hasher = Sha256::new();
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
 
-	let ok = compute_stream(input, &mut hasher)?;
-	hasher.update(salt.bytes());
+    fn finish(self) -> Result<Sha256Hash, error::Error>
+    {
+	Ok(self.hash())
+    }
+}
 
-	assert_eq!(array::copy_slice(&mut hash, hasher.finalize()), SHA256_SIZE);
-	Ok((ok, Self{hash}))
+impl Sha256Hash
+{
+    /// Compute a hash from a stream.
+    pub fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt) -> io::Result<(usize, Self)>
+    {
+	let mut state = Sha256State::new(salt);
+	let ok = provider::update_from(input, |bytes| state.update(bytes))?;
+	Ok((ok, state.hash()))
     }
 
     pub fn bytes(&self) -> &[u8; SHA256_SIZE]
@@ -70,15 +114,15 @@ impl fmt::Display for Sha256Hash
 
 impl provider::ByteProvider for hash::Sha256Hash
 {
+    type State = Sha256State;
+
     fn bytes(&self) -> &[u8]
     {
 	&self.bytes()[..]
     }
-    
-    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, done: &mut usize) -> Result<Self, error::Error>
+
+    fn digest_size() -> usize
     {
-	let (ok, this) = Self::compute(input, salt)?;
-	*done = ok;
-	Ok(this)
+	SHA256_SIZE
     }
 }
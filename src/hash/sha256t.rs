@@ -1,8 +1,12 @@
 use super::*;
 use crate::{
     array,
-    provider::ByteProvider,
+    provider::{
+	ByteProvider,
+	Incremental,
+    },
 };
+use sha2::Sha256;
 
 pub const SHA256_TRUNCATE: usize = 8;
 mod __static_assert
@@ -35,19 +39,42 @@ pub struct Sha256Truncated
 }
 
 
+/// The running state of a `Sha256Truncated`.
+pub struct Sha256TruncatedState(Salted<Sha256>);
+
+impl Incremental for Sha256TruncatedState
+{
+    type Output = Sha256Truncated;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Salted::new(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+
+    fn finish(self) -> Result<Sha256Truncated, error::Error>
+    {
+	let mut hash = [0u8; SHA256_TRUNCATE];
+	self.0.finish_into(&mut hash[..]);
+	Ok(Sha256Truncated{hash})
+    }
+}
+
 impl ByteProvider for hash::Sha256Truncated
 {
+    type State = Sha256TruncatedState;
+
     fn bytes(&self) -> &[u8]
     {
 	&self.hash[..]
     }
-    
-    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, done: &mut usize) -> Result<Self, error::Error>
+
+    fn digest_size() -> usize
     {
-	let (ok, sha) = Sha256Hash::compute(input, salt)?;
-	let mut hash = [0u8; SHA256_TRUNCATE];
-	array::copy_slice(&mut hash, sha.bytes());
-	*done = ok;
-	Ok(Self{hash})
+	SHA256_TRUNCATE
     }
 }
@@ -0,0 +1,75 @@
+use super::*;
+use sha3::Sha3_256;
+use provider::Incremental;
+
+pub const SHA3_256_SIZE: usize = 32;
+
+#[repr(C)]
+#[repr(packed)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Sha3_256Hash
+{
+    hash: [u8; SHA3_256_SIZE],
+}
+
+/// The running state of a `Sha3_256Hash`.
+pub struct Sha3_256State(Salted<Sha3_256>);
+
+impl Incremental for Sha3_256State
+{
+    type Output = Sha3_256Hash;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Salted::new(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+
+    fn finish(self) -> Result<Sha3_256Hash, error::Error>
+    {
+	let mut hash = [0u8; SHA3_256_SIZE];
+	self.0.finish_into(&mut hash[..]);
+	Ok(Sha3_256Hash{hash})
+    }
+}
+
+impl Sha3_256Hash
+{
+    pub fn bytes(&self) -> &[u8; SHA3_256_SIZE]
+    {
+	&self.hash
+    }
+}
+
+use std::fmt;
+impl fmt::Display for Sha3_256Hash
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Sha3_256hash (")?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
+
+impl provider::ByteProvider for Sha3_256Hash
+{
+    type State = Sha3_256State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.bytes()[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	SHA3_256_SIZE
+    }
+}
@@ -0,0 +1,146 @@
+use super::*;
+use sha2::{Sha512, Sha512Trunc256};
+use provider::Incremental;
+
+pub const SHA512_SIZE: usize = 64;
+pub const SHA512_256_SIZE: usize = 32;
+
+#[repr(C)]
+#[repr(packed)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Sha512Hash
+{
+    hash: [u8; SHA512_SIZE],
+}
+
+/// The running state of a `Sha512Hash`.
+pub struct Sha512State(Salted<Sha512>);
+
+impl Incremental for Sha512State
+{
+    type Output = Sha512Hash;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Salted::new(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+
+    fn finish(self) -> Result<Sha512Hash, error::Error>
+    {
+	let mut hash = [0u8; SHA512_SIZE];
+	self.0.finish_into(&mut hash[..]);
+	Ok(Sha512Hash{hash})
+    }
+}
+
+impl Sha512Hash
+{
+    pub fn bytes(&self) -> &[u8; SHA512_SIZE]
+    {
+	&self.hash
+    }
+}
+
+use std::fmt;
+impl fmt::Display for Sha512Hash
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Sha512hash (")?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
+
+impl provider::ByteProvider for Sha512Hash
+{
+    type State = Sha512State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.bytes()[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	SHA512_SIZE
+    }
+}
+
+/// SHA-512/256. This is not SHA512 truncated, it uses its own initial values.
+#[repr(C)]
+#[repr(packed)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Sha512_256Hash
+{
+    hash: [u8; SHA512_256_SIZE],
+}
+
+/// The running state of a `Sha512_256Hash`.
+pub struct Sha512_256State(Salted<Sha512Trunc256>);
+
+impl Incremental for Sha512_256State
+{
+    type Output = Sha512_256Hash;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Salted::new(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+
+    fn finish(self) -> Result<Sha512_256Hash, error::Error>
+    {
+	let mut hash = [0u8; SHA512_256_SIZE];
+	self.0.finish_into(&mut hash[..]);
+	Ok(Sha512_256Hash{hash})
+    }
+}
+
+impl Sha512_256Hash
+{
+    pub fn bytes(&self) -> &[u8; SHA512_256_SIZE]
+    {
+	&self.hash
+    }
+}
+
+impl fmt::Display for Sha512_256Hash
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Sha512_256hash (")?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
+
+impl provider::ByteProvider for Sha512_256Hash
+{
+    type State = Sha512_256State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.bytes()[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	SHA512_256_SIZE
+    }
+}
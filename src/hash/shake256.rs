@@ -0,0 +1,90 @@
+use super::*;
+use sha3::{
+    Shake256,
+    digest::{
+	Update,
+	ExtendableOutput,
+	XofReader,
+    },
+};
+use provider::Incremental;
+
+/// The output size of SHAKE256 when no length is requested.
+pub const SHAKE256_DEFAULT_SIZE: usize = 32;
+
+/// SHAKE256, with an output of any length.
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+pub struct Shake256Hash
+{
+    hash: Box<[u8]>,
+}
+
+/// The running state of a `Shake256Hash`.
+pub struct Shake256State
+{
+    hasher: Shake256,
+    salt: salt::Salt,
+}
+
+impl Incremental for Shake256State
+{
+    type Output = Shake256Hash;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self {
+	    hasher: Shake256::default(),
+	    salt: salt.clone(),
+	}
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.hasher.update(bytes)
+    }
+
+    fn finish(self) -> Result<Shake256Hash, error::Error>
+    {
+	Ok(Shake256Hash{hash: <Shake256Hash as provider::XofProvider>::finish_xof(self, SHAKE256_DEFAULT_SIZE)?})
+    }
+}
+
+impl provider::XofProvider for Shake256Hash
+{
+    fn finish_xof(mut state: Shake256State, length: usize) -> Result<Box<[u8]>, error::Error>
+    {
+	state.hasher.update(state.salt.bytes());
+	let mut output = vec![0u8; length].into_boxed_slice();
+	XofReader::read(&mut state.hasher.finalize_xof(), &mut output[..]);
+	Ok(output)
+    }
+}
+
+impl provider::ByteProvider for Shake256Hash
+{
+    type State = Shake256State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	SHAKE256_DEFAULT_SIZE
+    }
+}
+
+use std::fmt;
+impl fmt::Display for Shake256Hash
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Shake256hash[->{}] (", self.hash.len())?;
+	for byte in self.hash.iter()
+	{
+	    write!(f, "{:02x}", *byte)?;
+	}
+	write!(f, ")")
+    }
+}
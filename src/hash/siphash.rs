@@ -0,0 +1,74 @@
+use super::*;
+use siphasher::sip::SipHasher13;
+use sha2::{Sha256, Digest};
+use std::hash::Hasher;
+use provider::Incremental;
+
+pub const SIPHASH_KEY_SIZE: usize = 16;
+pub const SIPHASH13_SIZE: usize = 8;
+
+/// Derive the 128 bit SipHash key from the salt.
+///
+/// The key is the first 128 bits of the SHA256 hash of the salt's bytes, so salts of any size can be used.
+fn siphash_key(salt: &salt::Salt) -> [u8; SIPHASH_KEY_SIZE]
+{
+    let mut key = [0u8; SIPHASH_KEY_SIZE];
+    array::copy_slice(&mut key, Sha256::digest(salt.bytes()));
+    key
+}
+
+pub struct SipHash13Checksum
+{
+    hash: [u8; SIPHASH13_SIZE],
+}
+
+/// The running state of a `SipHash13Checksum`, keyed from the salt.
+pub struct SipHash13State(SipHasher13);
+
+impl Incremental for SipHash13State
+{
+    type Output = SipHash13Checksum;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(SipHasher13::new_with_key(&siphash_key(salt)))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.write(bytes)
+    }
+
+    fn finish(self) -> Result<SipHash13Checksum, error::Error>
+    {
+	Ok(SipHash13Checksum{hash: self.0.finish().to_le_bytes()})
+    }
+}
+
+impl provider::ByteProvider for SipHash13Checksum
+{
+    type State = SipHash13State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	SIPHASH13_SIZE
+    }
+}
+
+use std::fmt;
+impl fmt::Display for SipHash13Checksum
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "SipHash13checksum (")?;
+	for b in provider::ByteProvider::bytes(self) {
+	    write!(f, "{:02x}", *b)?;
+	}
+	write!(f, ")")
+    }
+}
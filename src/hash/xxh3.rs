@@ -0,0 +1,141 @@
+use super::*;
+use xxhash_rust::xxh3::Xxh3;
+use provider::Incremental;
+
+pub const XXH3_64_SIZE: usize = 8;
+pub const XXH3_128_SIZE: usize = 16;
+
+/// A running XXH3, with the salt to append to its input.
+struct Running
+{
+    hasher: Xxh3,
+    salt: salt::Salt,
+}
+
+impl Running
+{
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self {
+	    hasher: Xxh3::new(),
+	    salt: salt.clone(),
+	}
+    }
+
+    fn salted(mut self) -> Xxh3
+    {
+	self.hasher.update(self.salt.bytes());
+	self.hasher
+    }
+}
+
+pub struct Xxh3_64Checksum
+{
+    hash: [u8; XXH3_64_SIZE],
+}
+
+/// The running state of a `Xxh3_64Checksum`.
+pub struct Xxh3_64State(Running);
+
+impl Incremental for Xxh3_64State
+{
+    type Output = Xxh3_64Checksum;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Running::new(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.hasher.update(bytes)
+    }
+
+    fn finish(self) -> Result<Xxh3_64Checksum, error::Error>
+    {
+	Ok(Xxh3_64Checksum{hash: self.0.salted().digest().to_le_bytes()})
+    }
+}
+
+impl provider::ByteProvider for Xxh3_64Checksum
+{
+    type State = Xxh3_64State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	XXH3_64_SIZE
+    }
+}
+
+pub struct Xxh3_128Checksum
+{
+    hash: [u8; XXH3_128_SIZE],
+}
+
+/// The running state of a `Xxh3_128Checksum`.
+pub struct Xxh3_128State(Running);
+
+impl Incremental for Xxh3_128State
+{
+    type Output = Xxh3_128Checksum;
+
+    fn new(salt: &salt::Salt) -> Self
+    {
+	Self(Running::new(salt))
+    }
+
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.hasher.update(bytes)
+    }
+
+    fn finish(self) -> Result<Xxh3_128Checksum, error::Error>
+    {
+	Ok(Xxh3_128Checksum{hash: self.0.salted().digest128().to_le_bytes()})
+    }
+}
+
+impl provider::ByteProvider for Xxh3_128Checksum
+{
+    type State = Xxh3_128State;
+
+    fn bytes(&self) -> &[u8]
+    {
+	&self.hash[..]
+    }
+
+    fn digest_size() -> usize
+    {
+	XXH3_128_SIZE
+    }
+}
+
+use std::fmt;
+impl fmt::Display for Xxh3_64Checksum
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Xxh3_64checksum (")?;
+	for b in provider::ByteProvider::bytes(self) {
+	    write!(f, "{:02x}", *b)?;
+	}
+	write!(f, ")")
+    }
+}
+
+impl fmt::Display for Xxh3_128Checksum
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Xxh3_128checksum (")?;
+	for b in provider::ByteProvider::bytes(self) {
+	    write!(f, "{:02x}", *b)?;
+	}
+	write!(f, ")")
+    }
+}
@@ -0,0 +1,109 @@
+use crate::*;
+use std::io::{
+    self,
+    IoSlice,
+};
+
+/// An incremental kana hash.
+///
+/// Input is fed in pieces with `update` (or through `std::io::Write`), and the kana hash is produced by `finalize`.
+/// The result is the same as `generate` with the same context over all the input at once.
+///
+/// # Buffering
+/// Input is fed straight into the algorithm's running state (see `provider::Incremental`), except for `Argon2id` which needs all its input at once (see `hash::KdfState`.)
+///
+/// # Example
+/// ```
+/// # use khash::{Hasher, generate};
+/// let mut hasher = Hasher::new(&Default::default());
+/// hasher.update(b"Hello ");
+/// hasher.update(b"world!");
+/// assert_eq!(hasher.finalize().unwrap(), generate(&Default::default(), "Hello world!").unwrap());
+/// ```
+pub struct Hasher
+{
+    context: ctx::Context,
+    state: Box<dyn provider::DynState>,
+    written: usize,
+}
+
+impl Hasher
+{
+    /// Create a new hasher for this context.
+    pub fn new(context: &ctx::Context) -> Self
+    {
+	Self {
+	    context: context.clone(),
+	    state: context.begin(),
+	    written: 0,
+	}
+    }
+
+    /// The context this hasher uses
+    pub fn get_context(&self) -> &ctx::Context
+    {
+	&self.context
+    }
+
+    /// The number of bytes fed into this hasher so far
+    pub fn written(&self) -> usize
+    {
+	self.written
+    }
+
+    /// Feed more input into the hash.
+    pub fn update(&mut self, bytes: &[u8])
+    {
+	self.state.update(bytes);
+	self.written += bytes.len();
+    }
+
+    /// Feed a number of slices into the hash in order, returning the total number of bytes fed.
+    pub fn update_vectored(&mut self, bufs: &[IoSlice<'_>]) -> usize
+    {
+	bufs.iter().fold(0, |done, buf| {
+	    self.update(&buf[..]);
+	    done + buf.len()
+	})
+    }
+
+    /// Finish the hash and return its digest bytes.
+    pub fn finalize_bytes(self) -> Result<Box<[u8]>, error::Error>
+    {
+	self.context.truncate(self.state.finish()?.into_vec())
+    }
+
+    /// Finish the hash and return its kana.
+    pub fn finalize(self) -> Result<String, error::Error>
+    {
+	let options = *self.context.get_output();
+	kana(&self.finalize_bytes()?[..], &options)
+    }
+}
+
+impl Default for Hasher
+{
+    #[inline] fn default() -> Self
+    {
+	Self::new(&Default::default())
+    }
+}
+
+impl io::Write for Hasher
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+	self.update(buf);
+	Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize>
+    {
+	Ok(self.update_vectored(bufs))
+    }
+
+    #[inline] fn flush(&mut self) -> io::Result<()>
+    {
+	Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+//! JNI bindings for the Java class `khash.KanaHash` (requires the `jni` feature.)
+//!
+//! The Java source is in `java/`. Each `KanaHash` owns a boxed `ctx::Context` through a `long` handle, like the opaque `khash_context` handles of the C API.
+//! Errors are thrown as `khash.KanaHashException`; exceptions thrown by a Java `InputStream` are left to propagate.
+use super::*;
+use ::jni::{
+    JNIEnv,
+    objects::{
+	JByteArray,
+	JByteBuffer,
+	JClass,
+	JObject,
+	JString,
+	JValue,
+	ReleaseMode,
+    },
+    sys::{
+	jboolean,
+	jbyte,
+	jint,
+	jlong,
+	jstring,
+	JNI_FALSE,
+	JNI_TRUE,
+    },
+};
+use std::{
+    convert::TryFrom,
+    io,
+    panic::{
+	self,
+	AssertUnwindSafe,
+    },
+    ptr,
+};
+
+const EXCEPTION_CLASS: &str = "khash/KanaHashException";
+
+/// Why a native method failed
+enum Failure
+{
+    /// A kana-hash error, thrown as `KanaHashException`
+    Khash(error::Error),
+    /// A JNI call failed, possibly with a Java exception already pending
+    Jni(::jni::errors::Error),
+}
+
+impl From<error::Error> for Failure
+{
+    fn from(from: error::Error) -> Self
+    {
+	Self::Khash(from)
+    }
+}
+
+impl From<::jni::errors::Error> for Failure
+{
+    fn from(from: ::jni::errors::Error) -> Self
+    {
+	Self::Jni(from)
+    }
+}
+
+/// Run the body of a native method, throwing any failure or panic as a Java exception and returning `T::default()` instead.
+fn throwing<'local, T, F>(env: &mut JNIEnv<'local>, f: F) -> T
+where F: FnOnce(&mut JNIEnv<'local>) -> Result<T, Failure>,
+      T: Default
+{
+    let message = match panic::catch_unwind(AssertUnwindSafe(|| f(env))) {
+	Ok(Ok(value)) => return value,
+	Ok(Err(Failure::Khash(error))) => error.to_string(),
+	Ok(Err(Failure::Jni(error))) => format!("kana-hash error: jni: {}", error),
+	Err(_) => error::Error::Unknown.to_string(),
+    };
+    // An exception thrown from Java (e.g. by `InputStream.read`) takes precedence.
+    if !env.exception_check().unwrap_or(true) {
+	let _ = env.throw_new(EXCEPTION_CLASS, message);
+    }
+    T::default()
+}
+
+/// Borrow the context behind a handle.
+///
+/// # Safety
+/// `handle` must have come from `create` and not been destroyed. The Java class checks it is not `0`.
+unsafe fn context<'a>(handle: jlong) -> Result<&'a ctx::Context, Failure>
+{
+    (handle as *const ctx::Context).as_ref().ok_or(Failure::Khash(error::Error::NullPointer("handle")))
+}
+
+/// See `context`.
+unsafe fn context_mut<'a>(handle: jlong) -> Result<&'a mut ctx::Context, Failure>
+{
+    (handle as *mut ctx::Context).as_mut().ok_or(Failure::Khash(error::Error::NullPointer("handle")))
+}
+
+/// Strings are returned as `Some` so a failure can return `null`.
+fn new_string(env: &mut JNIEnv<'_>, string: String) -> Result<Option<jstring>, Failure>
+{
+    Ok(Some(env.new_string(string)?.into_raw()))
+}
+
+/// Reads from a Java `InputStream` through a reused `byte[]` buffer.
+struct StreamReader<'a, 'local>
+{
+    env: &'a mut JNIEnv<'local>,
+    stream: &'a JObject<'local>,
+    buffer: JByteArray<'local>,
+}
+
+impl<'a, 'local> StreamReader<'a, 'local>
+{
+    fn new(env: &'a mut JNIEnv<'local>, stream: &'a JObject<'local>) -> Result<Self, Failure>
+    {
+	let buffer = env.new_byte_array(BUFFER_SIZE as jint)?;
+	Ok(Self {
+	    env,
+	    stream,
+	    buffer,
+	})
+    }
+}
+
+impl io::Read for StreamReader<'_, '_>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+	let size = std::cmp::min(buf.len(), BUFFER_SIZE);
+	let read = self.env.call_method(self.stream, "read", "([BII)I", &[JValue::Object(&self.buffer), JValue::Int(0), JValue::Int(size as jint)])
+	    .and_then(|read| read.i())
+	    .map_err(io::Error::other)?;
+	if read <= 0 {
+	    // `-1` at the end of the stream
+	    return Ok(0);
+	}
+	let read = read as usize;
+	// SAFETY: `u8` and `jbyte` (`i8`) have the same layout.
+	let buf = unsafe { &mut *(&mut buf[..read] as *mut [u8] as *mut [jbyte]) };
+	self.env.get_byte_array_region(&self.buffer, 0, buf).map_err(io::Error::other)?;
+	Ok(read)
+    }
+}
+
+/// `private static native long create(int algorithm, int saltType, byte[] salt)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_create<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, algorithm: jint, salt_type: jint, salt: JByteArray<'local>) -> jlong
+{
+    throwing(&mut env, |env| {
+	let algo = ctx::Algorithm::from_ffi(u8::try_from(algorithm).map_err(|_| error::Error::UnknownAlgorithm(u8::MAX))?)?;
+	let salt = match u8::try_from(salt_type).unwrap_or(u8::MAX) {
+	    salt::SALT_TYPE_NONE => salt::Salt::none(),
+	    salt::SALT_TYPE_DEFAULT => salt::Salt::default(),
+	    salt::SALT_TYPE_RANDOM => salt::Salt::random().map_err(error::Error::from)?,
+	    salt::SALT_TYPE_SPECIFIC => {
+		if salt.is_null() {
+		    return Err(error::Error::NullPointer("salt").into());
+		}
+		let salt = env.convert_byte_array(&salt)?;
+		if salt.is_empty() {
+		    return Err(error::Error::EmptySalt.into());
+		}
+		salt::Salt::unfixed(&salt[..])
+	    },
+	    salt_type => return Err(error::Error::UnknownSaltType(salt_type).into()),
+	};
+	Ok(Box::into_raw(Box::new(ctx::Context::new(algo, salt))) as jlong)
+    })
+}
+
+/// `private static native long copy(long handle)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_copy<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong) -> jlong
+{
+    throwing(&mut env, |_| {
+	let context = unsafe { context(handle)? };
+	Ok(Box::into_raw(Box::new(context.clone())) as jlong)
+    })
+}
+
+/// `private static native void destroy(long handle)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_destroy<'local>(_: JNIEnv<'local>, _: JClass<'local>, handle: jlong)
+{
+    if handle != 0 {
+	drop(unsafe { Box::from_raw(handle as *mut ctx::Context) });
+    }
+}
+
+/// `private static native void setCost(long handle, int iterations, int memoryKib, int parallelism)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_setCost<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong, iterations: jint, memory_kib: jint, parallelism: jint)
+{
+    throwing(&mut env, |_| {
+	let unsigned = |value: jint| u32::try_from(value).map_err(|_| error::Error::Parameters(format!("cost parameters cannot be negative, got {}", value)));
+	let cost = ctx::Cost {
+	    iterations: unsigned(iterations)?,
+	    memory_kib: unsigned(memory_kib)?,
+	    parallelism: unsigned(parallelism)?,
+	};
+	unsafe { context_mut(handle)? }.set_cost(cost);
+	Ok(())
+    })
+}
+
+/// `private static native void setLength(long handle, int length)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_setLength<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong, length: jint)
+{
+    throwing(&mut env, |_| {
+	let length = match usize::try_from(length) {
+	    Ok(0) => None,
+	    Ok(length) => Some(length),
+	    Err(_) => return Err(error::Error::Parameters(format!("length cannot be negative, got {}", length)).into()),
+	};
+	unsafe { context_mut(handle)? }.set_length(length);
+	Ok(())
+    })
+}
+
+/// `private static native void setFlags(long handle, int flags)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_setFlags<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong, flags: jint)
+{
+    throwing(&mut env, |_| {
+	if flags as u32 as u64 & !ctx::OUTPUT_FLAGS_MASK != 0 {
+	    return Err(error::Error::Parameters(format!("unknown output flags {:#x}", flags)).into());
+	}
+	let output = ctx::OutputOptions::from_flags(flags as u32 as u64)?;
+	unsafe { context_mut(handle)? }.set_output(output);
+	Ok(())
+    })
+}
+
+/// `private static native int digestSize(long handle)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_digestSize<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong) -> jint
+{
+    throwing(&mut env, |_| Ok(unsafe { context(handle)? }.digest_size() as jint))
+}
+
+/// `private static native int kanaLength(long handle)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_kanaLength<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong) -> jint
+{
+    throwing(&mut env, |_| Ok(unsafe { context(handle)? }.kana_length() as jint))
+}
+
+/// `private static native String hashBytes(long handle, byte[] data, int offset, int length)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_hashBytes<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong, data: JByteArray<'local>, offset: jint, length: jint) -> jstring
+{
+    throwing(&mut env, |env| {
+	let context = unsafe { context(handle)? };
+	let hash = {
+	    // The JVM may pin the array instead of copying it.
+	    let elements = unsafe { env.get_array_elements(&data, ReleaseMode::NoCopyBack)? };
+	    let bytes = unsafe { std::slice::from_raw_parts(elements.as_ptr() as *const u8, elements.len()) };
+	    generate(context, &bytes[offset as usize..][..length as usize])?
+	};
+	new_string(env, hash)
+    }).unwrap_or(ptr::null_mut())
+}
+
+/// `private static native String hashDirect(long handle, ByteBuffer buffer, int position, int length)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_hashDirect<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong, buffer: JByteBuffer<'local>, position: jint, length: jint) -> jstring
+{
+    throwing(&mut env, |env| {
+	let context = unsafe { context(handle)? };
+	let address = env.get_direct_buffer_address(&buffer)?;
+	let capacity = env.get_direct_buffer_capacity(&buffer)?;
+	let (position, length) = (position as usize, length as usize);
+	if position + length > capacity {
+	    return Err(error::Error::Length{expected: position + length, got: capacity}.into());
+	}
+	// SAFETY: the buffer's memory is valid for `capacity` bytes while `buffer` is reachable, which it is for this call.
+	let bytes = unsafe { std::slice::from_raw_parts(address.add(position), length) };
+	let hash = generate(context, bytes)?;
+	new_string(env, hash)
+    }).unwrap_or(ptr::null_mut())
+}
+
+/// `private static native String hashStream(long handle, InputStream stream)`
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_hashStream<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, handle: jlong, stream: JObject<'local>) -> jstring
+{
+    throwing(&mut env, |env| {
+	let context = unsafe { context(handle)? };
+	let (_, hash) = generate_stream(context, &mut StreamReader::new(env, &stream)?)?;
+	new_string(env, hash)
+    }).unwrap_or(ptr::null_mut())
+}
+
+/// `private static native boolean verifyHash(String hash, String expected)`
+///
+/// Compares in constant time.
+#[no_mangle]
+pub extern "system" fn Java_khash_KanaHash_verifyHash<'local>(mut env: JNIEnv<'local>, _: JClass<'local>, hash: JString<'local>, expected: JString<'local>) -> jboolean
+{
+    throwing(&mut env, |env| {
+	let hash: String = env.get_string(&hash)?.into();
+	let expected: String = env.get_string(&expected)?.into();
+	Ok(if array::ct_eq(hash.as_bytes(), expected.as_bytes()) { JNI_TRUE } else { JNI_FALSE })
+    })
+}
@@ -4,13 +4,33 @@
 //! Mnemonics can be generated from slices or from streams.
 //!
 //! It has a Rust API documented here, as well as C FFI bindings and a C header (see `include/`.)
+//! With the `wasm` feature (and without the default `ffi` feature) it builds for `wasm32-unknown-unknown` with a wasm-bindgen JS API (see `wasm`.)
+//! The optional `jni` feature adds the native methods of the Java class `khash.KanaHash` (see `java/`.)
 //!
 //! ## Digest
 //! The digests available are:
 //! * SHA256 truncated to the first 64 bits (8 bytes) (default digest)
 //! * SHA256 full
-//! * CRC64 (requires "crc" default feature enabled)
+//! * CRC64, as CRC-64/XZ (requires "crc" default feature enabled)
 //! * CRC32 (requires "crc" default feature enabled)
+//! * CRC32C and CRC-64/GO-ISO (requires "crc" default feature enabled)
+//! * HMAC-SHA256 keyed with the salt, full or truncated to 64 bits
+//! * SHA512, SHA-512/256 and SHA3-256
+//! * xxHash3 64 and 128 bit (requires "xxh3" default feature enabled)
+//! * SipHash-1-3 keyed with the salt (requires "siphash" default feature enabled)
+//! * Argon2id, scrypt and PBKDF2-HMAC-SHA256 password hashing, with tunable cost (requires "kdf" default feature enabled)
+//! * BLAKE3, full or truncated to 64 bits (large inputs are hashed across threads with the "threads" default feature enabled)
+//! * SHAKE256
+//!
+//! ### Output length
+//! A context can request a specific digest length in bytes with `ctx::Context::with_length`.
+//! SHAKE256 and BLAKE3 are extendable output functions and can produce any length, the other digests are truncated to it.
+//!
+//! ### Incremental hashing
+//! When input arrives in pieces, a `Hasher` can be fed with `update` (or used as a `std::io::Write`) and finalized into the kana hash.
+//!
+//! ### Custom digests
+//! Other digests can be plugged in by implementing `provider::ByteProvider` (with a `provider::Incremental` running state) and using `generate_with`, or by implementing `provider::DynProvider` and using a context with `ctx::Algorithm::Custom`.
 //!
 //! ### Salting
 //! The salting options for the digests are:
@@ -19,7 +39,7 @@
 //! * Fixed runtime 32 byte salt
 //! * Dynamically sized runtime salt
 //! * No salt
-//! The salt (if any) is fed into the digest directly after all the data.
+//! The salt (if any) is fed into the digest directly after all the data, except for the HMAC and SipHash digests, which use the salt as (or to derive) their key.
 //! (See `ctx` and `salt` modules).
 //!
 //! ## Generating kana mnemonics from arbitrary data
@@ -30,6 +50,9 @@
 //! let input = "Hello world!";
 //! let mnemonic: String = Digest::new(&mut input.as_bytes()).collect(); // Read the bytes from the `input` string and collect the kana mnemonic into a `String` 
 //! ```
+//!
+//! ## Reversible kana encoding
+//! Mnemonics cannot be turned back into the data they came from. For a lossless encoding with a decoder, see the `codec` module.
 
 #![cfg_attr(nightly, feature(test))] 
 #![allow(dead_code)]
@@ -42,6 +65,7 @@ use std::{
 	Read,
     },
     fmt::Write,
+    convert::TryFrom,
 };
 
 //type HASHER = hash::Crc64Checksum; //was unused?
@@ -168,6 +192,245 @@ mod tests {
 	Ok(())
     }
     #[test]
+    fn codec_roundtrip() -> Result<(), error::Error>
+    {
+	let mut buffer = [0u8; 64];
+	getrandom::getrandom(&mut buffer[..]).unwrap();
+	for len in 0..buffer.len()
+	{
+	    let kana = codec::encode(&buffer[..len]);
+	    assert_eq!(kana.chars().count(), codec::encoded_length(len));
+	    assert_eq!(&codec::decode(&kana)?[..], &buffer[..len]);
+	}
+
+	assert!(matches!(codec::decode("あいx"), Err(error::Error::InvalidKana{position: 2, found: Some('x')})));
+	assert!(matches!(codec::decode(codec::encode(b"hello") + "あ"), Err(error::Error::Truncated)));
+	assert!(matches!(codec::decode("ォォ"), Err(error::Error::InvalidKana{position: 0, found: None})));
+	Ok(())
+    }
+    #[test]
+    fn verify_kana() -> Result<(), error::Error>
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let context = ctx::Context::default();
+	assert!(verify(&context, input, "もシちゅゆをヌョ")?);
+	assert!(!verify(&context, input, "もシちゅゆをヌ")?);
+	assert!(!verify(&context, input, "もシちゅゆをヌャ")?);
+	assert!(!verify(&context, input, "もシちゅゆをヌョあ")?);
+	assert!(!verify(&context, input, "")?);
+	assert_eq!(verify_stream(&context, &mut &input[..], "もシちゅゆをヌョ")?, (input.len(), true));
+	Ok(())
+    }
+    #[test]
+    fn hmac_sha256() -> Result<(), error::Error>
+    {
+	use provider::ByteProvider;
+	// RFC 4231 test case 2
+	let (_, mac) = provider::compute::<_, hash::HmacSha256>(&mut &b"what do ya want for nothing?"[..], salt::Salt::unfixed(b"Jefe"))?;
+	assert_eq!(mac.bytes(), &hex_literal::hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")[..]);
+	Ok(())
+    }
+    #[test]
+    fn blake3_threads() -> Result<(), error::Error>
+    {
+	let mut input = vec![0u8; hash::BLAKE3_THREAD_THRESHOLD * 4 + 3];
+	getrandom::getrandom(&mut input[..]).unwrap();
+	for algo in [ctx::Algorithm::Blake3, ctx::Algorithm::Blake3Truncated].iter()
+	{
+	    let context = ctx::Context::new(algo.clone(), salt::Salt::default());
+	    let (read, stream) = generate_stream(&context, &mut &input[..])?;
+	    assert_eq!(read, input.len());
+	    assert_eq!(generate(&context, &input)?, stream);
+	}
+	Ok(())
+    }
+    #[test]
+    fn blake3_known_answer() -> Result<(), error::Error>
+    {
+	use provider::ByteProvider;
+	const EMPTY: [u8; hash::BLAKE3_SIZE] = hex_literal::hex!("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+	let (_, full) = provider::compute::<_, hash::Blake3Hash>(&mut &b""[..], salt::Salt::none())?;
+	assert_eq!(full.bytes(), &EMPTY);
+	let (_, truncated) = provider::compute::<_, hash::Blake3Truncated>(&mut &b""[..], salt::Salt::none())?;
+	assert_eq!(truncated.bytes(), &EMPTY[..8]);
+	for (algo, expected) in [(ctx::Algorithm::Blake3, &EMPTY[..]), (ctx::Algorithm::Blake3Truncated, &EMPTY[..8])].iter() {
+	    assert_eq!(generate(&ctx::Context::new(algo.clone(), salt::Salt::none()), b"")?, kana(expected, &Default::default())?);
+	}
+	Ok(())
+    }
+    #[test]
+    fn sha2_sha3() -> Result<(), error::Error>
+    {
+	use provider::ByteProvider;
+	let (_, sha512) = provider::compute::<_, hash::Sha512Hash>(&mut &b"abc"[..], salt::Salt::none())?;
+	assert_eq!(sha512.bytes(), &hex_literal::hex!("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f")[..]);
+	let (_, sha512_256) = provider::compute::<_, hash::Sha512_256Hash>(&mut &b"abc"[..], salt::Salt::none())?;
+	assert_eq!(sha512_256.bytes(), &hex_literal::hex!("53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23")[..]);
+	let (_, sha3) = provider::compute::<_, hash::Sha3_256Hash>(&mut &b"abc"[..], salt::Salt::none())?;
+	assert_eq!(sha3.bytes(), &hex_literal::hex!("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")[..]);
+	Ok(())
+    }
+    #[test]
+    #[cfg(feature="xxh3")]
+    fn xxh3_reference() -> Result<(), error::Error>
+    {
+	use provider::ByteProvider;
+	let (_, xxh3_64) = provider::compute::<_, hash::Xxh3_64Checksum>(&mut &b""[..], salt::Salt::none())?;
+	assert_eq!(xxh3_64.bytes(), &0x2D06800538D394C2u64.to_le_bytes());
+	let (_, xxh3_128) = provider::compute::<_, hash::Xxh3_128Checksum>(&mut &b""[..], salt::Salt::none())?;
+	assert_eq!(xxh3_128.bytes(), &0x99AA06D3014798D86001C324468D497Fu128.to_le_bytes());
+	Ok(())
+    }
+    #[test]
+    #[cfg(feature="siphash")]
+    fn siphash_keyed() -> Result<(), error::Error>
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let a = generate(&ctx::Context::new(ctx::Algorithm::SipHash13, salt::Salt::unfixed(b"deployment a")), input)?;
+	let b = generate(&ctx::Context::new(ctx::Algorithm::SipHash13, salt::Salt::unfixed(b"deployment b")), input)?;
+	assert_ne!(a, b);
+	assert_eq!(a, generate(&ctx::Context::new(ctx::Algorithm::SipHash13, salt::Salt::unfixed(b"deployment a")), input)?);
+	Ok(())
+    }
+    #[test]
+    #[cfg(feature="crc")]
+    fn crc_polynomials() -> Result<(), error::Error>
+    {
+	use provider::ByteProvider;
+	fn check<P: ByteProvider>(expected: &[u8]) -> Result<(), error::Error>
+	{
+	    let (_, crc) = provider::compute::<_, P>(&mut &b"123456789"[..], salt::Salt::none())?;
+	    assert_eq!(crc.bytes(), expected);
+	    Ok(())
+	}
+	check::<hash::Crc32Checksum>(&0xcbf43926u32.to_ne_bytes())?;
+	check::<hash::Crc32cChecksum>(&0xe3069283u32.to_ne_bytes())?;
+	check::<hash::Crc64Checksum>(&0x995dc9bbdf1939fau64.to_ne_bytes())?;
+	check::<hash::Crc64IsoChecksum>(&0xb90956c775a41001u64.to_ne_bytes())
+    }
+    #[test]
+    #[cfg(feature="kdf")]
+    fn kdf_cost() -> Result<(), error::Error>
+    {
+	let input = b"1234";
+	let salt = salt::Salt::unfixed(b"user@example.com");
+	let cost = ctx::Cost{iterations: 1, memory_kib: 64, parallelism: 1};
+	for algo in [ctx::Algorithm::Argon2id, ctx::Algorithm::Scrypt, ctx::Algorithm::Pbkdf2].iter()
+	{
+	    let context = ctx::Context::new_with_cost(algo.clone(), salt.clone(), cost);
+	    let kana = generate(&context, input)?;
+	    assert_eq!(generate_stream(&context, &mut &input[..])?, (input.len(), kana.clone()));
+
+	    let harder = ctx::Context::new_with_cost(algo.clone(), salt.clone(), ctx::Cost{iterations: 2, memory_kib: 128, ..cost});
+	    assert_ne!(generate(&harder, input)?, kana);
+	}
+	assert_eq!(ctx::Cost::from_flags(cost.into_flags()?), cost);
+	assert!(matches!(ctx::Cost{iterations: 1 << 21, ..cost}.into_flags(), Err(error::Error::Parameters(_))));
+	assert!(matches!(generate(&ctx::Context::new_with_cost(ctx::Algorithm::Argon2id, salt::Salt::none(), cost), input), Err(error::Error::Parameters(_))));
+	Ok(())
+    }
+    #[test]
+    fn output_length() -> Result<(), error::Error>
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let truncated = |algo: ctx::Algorithm, length| generate(&ctx::Context::new(algo, salt::Salt::default()).with_length(length), input);
+
+	assert_eq!(truncated(ctx::Algorithm::Sha256, 8)?, generate(&ctx::Context::default(), input)?);
+	assert_eq!(truncated(ctx::Algorithm::Blake3, 32)?, generate(&ctx::Context::new(ctx::Algorithm::Blake3, salt::Salt::default()), input)?);
+	assert_eq!(truncated(ctx::Algorithm::Blake3, 100)?.chars().count(), 100);
+	assert!(truncated(ctx::Algorithm::Blake3, 100)?.starts_with(&truncated(ctx::Algorithm::Blake3, 40)?));
+	assert_eq!(truncated(ctx::Algorithm::Shake256, 6)?.chars().count(), 6);
+	assert!(truncated(ctx::Algorithm::Shake256, 12)?.starts_with(&truncated(ctx::Algorithm::Shake256, 6)?));
+	assert!(matches!(truncated(ctx::Algorithm::Sha256, 33), Err(error::Error::Parameters(_))));
+	assert!(matches!(truncated(ctx::Algorithm::Shake256, 0), Err(error::Error::Parameters(_))));
+	Ok(())
+    }
+    #[test]
+    fn custom_provider() -> Result<(), error::Error>
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let salt = salt::Salt::default();
+	let custom = ctx::Context::new(ctx::Algorithm::Custom(provider::of::<hash::Sha256Hash>()), salt.clone());
+	let expected = generate(&ctx::Context::new(ctx::Algorithm::Sha256, salt.clone()), input)?;
+
+	assert_eq!(generate_with::<hash::Sha256Hash, _>(&salt, input)?, expected);
+	assert_eq!(generate(&custom, input)?, expected);
+	assert_eq!(generate_stream(&custom, &mut &input[..])?, (input.len(), expected));
+	assert_eq!(custom.digest_size(), 32);
+	assert_eq!(custom.clone(), custom);
+	assert_ne!(ctx::Algorithm::Custom(provider::of::<hash::Sha256Hash>()), *custom.get_algorithm());
+	assert_eq!(provider::of::<hash::Shake256Hash>().digest_size(), hash::SHAKE256_DEFAULT_SIZE);
+	assert!(u8::try_from(custom.get_algorithm().clone()).is_err());
+	#[cfg(feature="ffi")]
+	assert!(matches!(unsafe { custom.into_raw() }, Err(error::Error::Parameters(_))));
+	Ok(())
+    }
+    #[test]
+    fn incremental() -> Result<(), error::Error>
+    {
+	use std::io::{self, IoSlice};
+	let input: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+	let salt = salt::Salt::random()?;
+
+	let mut contexts = [#[cfg(feature="crc")] ctx::Algorithm::Crc32,
+			#[cfg(feature="crc")] ctx::Algorithm::Crc64,
+			#[cfg(feature="crc")] ctx::Algorithm::Crc32c,
+			#[cfg(feature="crc")] ctx::Algorithm::Crc64Iso,
+			ctx::Algorithm::Sha256,
+			ctx::Algorithm::Sha256Truncated,
+			ctx::Algorithm::HmacSha256,
+			ctx::Algorithm::HmacSha256Truncated,
+			ctx::Algorithm::Blake3,
+			ctx::Algorithm::Blake3Truncated,
+			ctx::Algorithm::Sha512,
+			ctx::Algorithm::Sha512_256,
+			ctx::Algorithm::Sha3_256,
+			#[cfg(feature="xxh3")] ctx::Algorithm::Xxh3_64,
+			#[cfg(feature="xxh3")] ctx::Algorithm::Xxh3_128,
+			#[cfg(feature="siphash")] ctx::Algorithm::SipHash13,
+			ctx::Algorithm::Shake256,
+			ctx::Algorithm::Custom(provider::of::<hash::Sha256Hash>())].iter()
+	    .map(|algo| ctx::Context::new(algo.clone(), salt.clone()))
+	    .chain([ctx::Algorithm::Blake3, ctx::Algorithm::Shake256, ctx::Algorithm::Sha512].iter().map(|algo| ctx::Context::new(algo.clone(), salt.clone()).with_length(40)))
+	    .collect::<Vec<_>>();
+	#[cfg(feature="kdf")] let cost = ctx::Cost{iterations: 1, memory_kib: 64, parallelism: 1};
+	#[cfg(feature="kdf")] contexts.extend([ctx::Algorithm::Argon2id, ctx::Algorithm::Scrypt, ctx::Algorithm::Pbkdf2].iter().map(|algo| ctx::Context::new_with_cost(algo.clone(), salt.clone(), cost)));
+
+	for context in contexts.iter()
+	{
+	    let expected = generate(context, &input[..])?;
+
+	    let mut hasher = Hasher::new(context);
+	    for chunk in input.chunks(7919) {
+		hasher.update(chunk);
+	    }
+	    assert_eq!(hasher.written(), input.len());
+	    assert_eq!(hasher.finalize()?, expected, "{:?}", context);
+
+	    let mut hasher = Hasher::new(context);
+	    assert_eq!(io::copy(&mut &input[..], &mut hasher)?, input.len() as u64);
+	    assert_eq!(hasher.finalize()?, expected, "{:?}", context);
+
+	    let mut hasher = Hasher::new(context);
+	    let (a, b) = input.split_at(1000);
+	    assert_eq!(hasher.update_vectored(&[IoSlice::new(a), IoSlice::new(&[]), IoSlice::new(b)]), input.len());
+	    assert_eq!(hasher.finalize()?, expected, "{:?}", context);
+	}
+	// Inputs around the HMAC-SHA256 block size, which PBKDF2 and scrypt stop buffering after
+	#[cfg(feature="kdf")]
+	for algo in [ctx::Algorithm::Scrypt, ctx::Algorithm::Pbkdf2].iter() {
+	    let context = ctx::Context::new_with_cost(algo.clone(), salt.clone(), cost);
+	    for &length in [64, 65].iter() {
+		let mut hasher = Hasher::new(&context);
+		input[..length].iter().for_each(|byte| hasher.update(&[*byte]));
+		assert_eq!(hasher.finalize()?, generate(&context, &input[..length])?, "{:?}", context);
+		assert_eq!(generate_stream(&context, &mut &input[..length])?.1, generate(&context, &input[..length])?);
+	    }
+	}
+	assert!(matches!(Hasher::new(&ctx::Context::default().with_length(0)).finalize(), Err(error::Error::Parameters(_))));
+	Ok(())
+    }
+    #[test]
     fn rng()
     {
 	let input = b"loli";
@@ -180,6 +443,464 @@ mod tests {
     }
 
 
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_stream()
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let context = ctx::Context::new(ctx::Algorithm::Sha256, salt::Salt::default());
+	let expected = generate(&context, input).unwrap();
+	unsafe {
+	    let raw = context.into_raw().unwrap();
+	    let raw = &raw as *const ctx::CContext as *const c_void;
+	    let mut stream = std::ptr::null_mut();
+	    assert_eq!(khash_stream_new(raw, &mut stream), GENERIC_SUCCESS);
+	    for chunk in input.chunks(5) {
+		assert_eq!(khash_stream_update(stream, chunk.as_ptr() as *const c_void, chunk.len()), GENERIC_SUCCESS);
+	    }
+	    assert_eq!(khash_stream_update(stream, std::ptr::null(), 0), GENERIC_SUCCESS);
+
+	    let mut output = vec![0u8; expected.len() + 1];
+	    let mut length = 0;
+	    assert_eq!(khash_stream_finish(stream, output.as_mut_ptr() as *mut c_char, output.len(), &mut length), GENERIC_SUCCESS);
+	    assert_eq!(length, expected.len());
+	    assert_eq!(std::ffi::CStr::from_bytes_with_nul(&output[..]).unwrap().to_str().unwrap(), expected);
+
+	    assert_eq!(khash_stream_update(stream, input.as_ptr() as *const c_void, input.len()), 7);
+	    assert_eq!(khash_stream_finish(stream, output.as_mut_ptr() as *mut c_char, output.len(), std::ptr::null_mut()), 7);
+	    assert_eq!(khash_stream_free(stream), GENERIC_SUCCESS);
+	    assert_eq!(khash_free_context(raw as *mut c_void), GENERIC_SUCCESS);
+	}
+    }
+
+    #[test]
+    fn kana_utf8_size()
+    {
+	for kana in map::KANA.iter().chain(map::KANA_SUB.iter()).chain(map::KANA_SWAP.iter().chain(map::KANA_SWAP2.iter()).flatten()) {
+	    assert_eq!(kana.len_utf8(), KANA_UTF8_SIZE, "{}", kana);
+	}
+	for length in 1..10 {
+	    let context = ctx::Context::new(ctx::Algorithm::Shake256, salt::Salt::default()).with_length(length);
+	    assert_eq!(generate(&context, "owo").unwrap().len(), kana_length(length));
+	}
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_truncate()
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let context = ctx::Context::new(ctx::Algorithm::Sha256Truncated, salt::Salt::default());
+	let expected = generate(&context, input).unwrap();
+	unsafe {
+	    let raw = context.into_raw().unwrap();
+	    let raw = &raw as *const ctx::CContext as *const c_void;
+	    let mut max_len = 0;
+	    assert_eq!(khash_context_max_length(raw, input.len(), &mut max_len), GENERIC_SUCCESS);
+	    assert_eq!(max_len, expected.len());
+
+	    for size in 0..expected.len() + 2 {
+		let mut output = vec![0xffu8; size];
+		let mut length = 0;
+		let mut stream = std::ptr::null_mut();
+		assert_eq!(khash_stream_new(raw, &mut stream), GENERIC_SUCCESS);
+		assert_eq!(khash_stream_update(stream, input.as_ptr() as *const c_void, input.len()), GENERIC_SUCCESS);
+		let rc = khash_stream_finish(stream, if size == 0 { std::ptr::null_mut() } else { output.as_mut_ptr() as *mut c_char }, size, &mut length);
+		assert_eq!(khash_stream_free(stream), GENERIC_SUCCESS);
+
+		assert_eq!(length, expected.len());
+		// Room is always left for the NUL terminator
+		let written = (size.saturating_sub(1).min(expected.len()) / KANA_UTF8_SIZE) * KANA_UTF8_SIZE;
+		assert_eq!(&output[..written], &expected.as_bytes()[..written]);
+		if size > 0 {
+		    assert_eq!(output[written], 0);
+		}
+		if size <= expected.len() {
+		    assert_eq!(rc, 8);
+		} else {
+		    assert_eq!(rc, GENERIC_SUCCESS);
+		}
+	    }
+	    assert_eq!(khash_free_context(raw as *mut c_void), GENERIC_SUCCESS);
+	}
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_handles()
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let salt = b"salt!";
+	let context = ctx::Context::new(ctx::Algorithm::Sha256, salt::Salt::unfixed(&salt[..]));
+	let expected = generate(&context, input).unwrap();
+	assert_eq!(khash_abi_version(), c::ABI_VERSION);
+	unsafe {
+	    let hash = |handle: *const c_void| {
+		let mut output = std::ptr::null_mut();
+		let mut length = 0;
+		assert_eq!(khash_context_hash(handle, input.as_ptr() as *const c_void, input.len(), &mut output, &mut length), GENERIC_SUCCESS);
+		let string = std::ffi::CStr::from_ptr(output).to_str().unwrap().to_owned();
+		assert_eq!(length, string.len());
+		assert_eq!(khash_free_string(output), GENERIC_SUCCESS);
+		string
+	    };
+
+	    let mut handle = std::ptr::null_mut();
+	    assert_eq!(khash_context_create(&mut handle), GENERIC_SUCCESS);
+	    assert_eq!(hash(handle), generate(&Default::default(), input).unwrap());
+	    assert_eq!(khash_context_set_algorithm(handle, 3), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_set_salt(handle, salt::SALT_TYPE_SPECIFIC, salt.as_ptr() as *const c_void, salt.len()), GENERIC_SUCCESS);
+	    assert_eq!(hash(handle), expected);
+
+	    let mut clone = std::ptr::null_mut();
+	    assert_eq!(khash_context_clone(handle, &mut clone), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_set_length(handle, 4), GENERIC_SUCCESS);
+	    assert_eq!(hash(handle), generate(&context.clone().with_length(4), input).unwrap());
+	    assert_eq!(khash_context_set_length(handle, ctx::MAX_FFI_LENGTH + 1), 7);
+	    assert_eq!(hash(handle), generate(&context.clone().with_length(4), input).unwrap());
+	    assert_eq!(hash(clone), expected);
+
+	    let mut stream = std::ptr::null_mut();
+	    assert_eq!(khash_context_stream_new(clone, &mut stream), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_destroy(clone), GENERIC_SUCCESS);
+	    assert_eq!(khash_stream_update(stream, input.as_ptr() as *const c_void, input.len()), GENERIC_SUCCESS);
+	    let mut output = vec![0u8; expected.len() + 1];
+	    assert_eq!(khash_stream_finish(stream, output.as_mut_ptr() as *mut c_char, output.len(), std::ptr::null_mut()), GENERIC_SUCCESS);
+	    assert_eq!(std::ffi::CStr::from_bytes_with_nul(&output[..]).unwrap().to_str().unwrap(), expected);
+	    assert_eq!(khash_stream_free(stream), GENERIC_SUCCESS);
+
+	    assert_eq!(khash_context_destroy(handle), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_destroy(std::ptr::null_mut()), GENERIC_SUCCESS);
+	    assert_ne!(khash_context_set_algorithm(std::ptr::null_mut(), 3), GENERIC_SUCCESS);
+	}
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_input()
+    {
+	let input = vec![0x55u8; 1024 * 1024];
+	let context = ctx::Context::new(ctx::Algorithm::Blake3, salt::Salt::default());
+	let expected = generate(&context, &input).unwrap();
+	unsafe {
+	    let raw = context.into_raw().unwrap();
+	    let ptr = &raw as *const ctx::CContext as *const c_void;
+	    let mut length = 0;
+	    assert_eq!(khash_length(ptr, input.as_ptr() as *const c_void, input.len(), &mut length), GENERIC_SUCCESS);
+	    assert_eq!(length, expected.len());
+	    assert_eq!(khash_length(ptr, std::ptr::null(), input.len(), &mut length), 9);
+	    assert_eq!(khash_length(ptr, input.as_ptr() as *const c_void, 0, &mut length), 10);
+
+	    let mut valid = false;
+	    let expected_c = std::ffi::CString::new(expected.clone()).unwrap();
+	    assert_eq!(khash_verify(ptr, std::ptr::null(), 0, expected_c.as_ptr(), &mut valid), 9);
+	    assert_eq!(khash_verify(ptr, input.as_ptr() as *const c_void, input.len(), expected_c.as_ptr(), &mut valid), GENERIC_SUCCESS);
+	    assert!(valid);
+
+	    let mut stream = std::ptr::null_mut();
+	    assert_eq!(khash_stream_new(ptr, &mut stream), GENERIC_SUCCESS);
+	    assert_eq!(khash_stream_update(stream, std::ptr::null(), 1), 9);
+	    assert_eq!(khash_stream_free(stream), GENERIC_SUCCESS);
+
+	    let mut output = vec![0u8; expected.len() + 1];
+	    let mut consumed = ctx::Context::clone_from_raw(&raw).unwrap().into_raw().unwrap();
+	    assert_eq!(khash_do(&mut consumed as *mut ctx::CContext as *mut c_void, input.as_ptr() as *const c_void, 0, output.as_mut_ptr() as *mut c_char, output.len()), 10);
+	    assert_eq!(khash_do(ptr as *mut c_void, input.as_ptr() as *const c_void, input.len(), output.as_mut_ptr() as *mut c_char, output.len()), GENERIC_SUCCESS);
+	    assert_eq!(std::ffi::CStr::from_ptr(output.as_ptr() as *const c_char).to_str().unwrap(), expected);
+	}
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_validation()
+    {
+	let salt = [0x55u8; salt::MAX_FFI_SALT_SIZE + 1];
+	let salt_ptr = salt.as_ptr() as *const c_void;
+	unsafe {
+	    let mut raw = std::mem::MaybeUninit::<ctx::CContext>::uninit();
+	    let out = raw.as_mut_ptr() as *mut c_void;
+	    assert_eq!(khash_new_context(200, salt::SALT_TYPE_DEFAULT, std::ptr::null(), 0, out), 11);
+	    assert_eq!(khash_new_context(3, 200, std::ptr::null(), 0, out), 12);
+	    assert_eq!(khash_new_context(3, salt::SALT_TYPE_SPECIFIC, salt_ptr, salt.len(), out), 13);
+	    assert_eq!(khash_new_context(3, salt::SALT_TYPE_SPECIFIC, salt_ptr, 0, out), 14);
+	    assert_eq!(khash_new_context(3, salt::SALT_TYPE_SPECIFIC, std::ptr::null(), 4, out), 9);
+	    assert_eq!(khash_new_context(3, salt::SALT_TYPE_DEFAULT, std::ptr::null(), 0, std::ptr::null_mut()), 9);
+	    assert_eq!(khash_new_context(3, salt::SALT_TYPE_SPECIFIC, salt_ptr, salt::MAX_FFI_SALT_SIZE, out), GENERIC_SUCCESS);
+	    assert_eq!(khash_free_context(out), GENERIC_SUCCESS);
+
+	    let mut salt_raw = std::mem::MaybeUninit::<salt::FFI>::uninit();
+	    assert_eq!(khash_new_salt(salt::SALT_TYPE_SPECIFIC, salt_ptr, 0, salt_raw.as_mut_ptr() as *mut c_void), 14);
+
+	    let mut length = 0;
+	    assert_eq!(khash_max_length(200, 0, &mut length), 11);
+	    assert_eq!(khash_max_length(3, 0, std::ptr::null_mut()), 9);
+	    assert_eq!(khash_length(std::ptr::null(), salt_ptr, 1, &mut length), 9);
+
+	    let mut handle = std::ptr::null_mut();
+	    assert_eq!(khash_context_create(&mut handle), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_set_algorithm(handle, 200), 11);
+	    assert_eq!(khash_context_set_salt(handle, salt::SALT_TYPE_SPECIFIC, salt_ptr, 0), 14);
+	    assert_eq!(khash_context_set_cost(handle, 1 << 21, 0, 0), 7);
+	    assert_eq!(khash_context_set_cost(handle, 0, 96, 0), 7);
+	    assert_eq!(khash_context_set_cost(handle, 0, 0, 32), 7);
+	    assert_eq!(khash_context_set_cost(handle, (1 << 21) - 1, 1 << 31, 31), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_destroy(handle), GENERIC_SUCCESS);
+	}
+    }
+
+    #[test]
+    fn output_options()
+    {
+	let input = "owowowoakpwodkapowkdapowkdpaokwpdoakwd";
+	let mixed = generate(&ctx::Context::new(ctx::Algorithm::Sha256, salt::Salt::default()), input).unwrap();
+	for &script in &[ctx::Script::Mixed, ctx::Script::Hiragana, ctx::Script::Katakana] {
+	    for &separators in &[false, true] {
+		for length in 1..10 {
+		    let output = ctx::OutputOptions{script, separators, ..Default::default()};
+		    let context = ctx::Context::new(ctx::Algorithm::Shake256, salt::Salt::default()).with_length(length).with_output(output);
+		    let hash = generate(&context, input).unwrap();
+		    assert_eq!(hash.len(), context.kana_length());
+		    assert_eq!(hash.split(ctx::OutputOptions::SEPARATOR).count(), if separators { length.div_ceil(2) } else { 1 });
+		    let kana = hash.chars().filter(|&c| c != ctx::OutputOptions::SEPARATOR);
+		    match script {
+			ctx::Script::Hiragana => kana.for_each(|c| assert!(('\u{3041}'..='\u{3096}').contains(&c), "{}", hash)),
+			ctx::Script::Katakana => kana.for_each(|c| assert!(('\u{30a1}'..='\u{30f6}').contains(&c), "{}", hash)),
+			ctx::Script::Mixed => (),
+		    }
+		}
+	    }
+	}
+
+	let context = ctx::Context::new(ctx::Algorithm::Sha256, salt::Salt::default()).with_output(ctx::OutputOptions{script: ctx::Script::Katakana, ..Default::default()});
+	let katakana = generate(&context, input).unwrap();
+	assert_eq!(katakana, mixed.chars().map(|c| ctx::Script::Katakana.apply(c)).collect::<String>());
+	let mut hasher = Hasher::new(&context);
+	hasher.update(input.as_bytes());
+	assert_eq!(hasher.finalize().unwrap(), katakana);
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_flags()
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let output = ctx::OutputOptions{script: ctx::Script::Hiragana, separators: true, nul_terminator: false};
+	let context = ctx::Context::new(ctx::Algorithm::Sha256, salt::Salt::default()).with_output(output);
+	let expected = generate(&context, input).unwrap();
+	unsafe {
+	    let mut raw = context.clone().into_raw().unwrap();
+	    let ptr = &mut raw as *mut ctx::CContext as *mut c_void;
+	    assert_eq!(ctx::Context::clone_from_raw(&raw).unwrap(), context);
+
+	    let mut max_len = 0;
+	    assert_eq!(khash_context_max_length(ptr, 0, &mut max_len), GENERIC_SUCCESS);
+	    assert_eq!(max_len, expected.len());
+
+	    let mut buffer = vec![0xffu8; expected.len() + 1];
+	    assert_eq!(khash_do(ptr, input.as_ptr() as *const c_void, input.len(), buffer.as_mut_ptr() as *mut c_char, buffer.len()), GENERIC_SUCCESS);
+	    assert_eq!(std::str::from_utf8(&buffer[..expected.len()]).unwrap(), expected);
+	    assert_eq!(buffer[expected.len()], 0xff);
+
+	    let mut handle = std::ptr::null_mut();
+	    assert_eq!(khash_context_create(&mut handle), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_set_algorithm(handle, 3), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_set_flags(handle, 1 | 4 | 8), GENERIC_SUCCESS);
+	    let mut string = std::ptr::null_mut();
+	    assert_eq!(khash_context_hash(handle, input.as_ptr() as *const c_void, input.len(), &mut string, std::ptr::null_mut()), GENERIC_SUCCESS);
+	    assert_eq!(std::ffi::CStr::from_ptr(string).to_str().unwrap(), expected);
+	    assert_eq!(khash_free_string(string), GENERIC_SUCCESS);
+	    assert_eq!(khash_context_set_flags(handle, 1 | 2), 7);
+	    assert_eq!(khash_context_set_flags(handle, 1 << 4), 7);
+	    assert_eq!(khash_context_destroy(handle), GENERIC_SUCCESS);
+
+	    let long = ctx::Context::new(ctx::Algorithm::Shake256, salt::Salt::default());
+	    let mut raw = long.clone().with_length(ctx::MAX_FFI_LENGTH).into_raw().unwrap();
+	    assert_eq!(ctx::Context::clone_from_raw(&raw).unwrap().digest_size(), ctx::MAX_FFI_LENGTH);
+	    assert_eq!(khash_free_context(&mut raw as *mut ctx::CContext as *mut c_void), GENERIC_SUCCESS);
+	    assert!(matches!(long.with_length(ctx::MAX_FFI_LENGTH + 1).into_raw(), Err(error::Error::Parameters(_))));
+	}
+    }
+
+    #[test]
+    fn algorithms()
+    {
+	let all = ctx::Algorithm::all();
+	// Keep the password hashes cheap
+	let cost = ctx::Cost{iterations: 1, memory_kib: 16, parallelism: 1};
+	assert!(all.contains(&ctx::Algorithm::default()));
+	for (i, algo) in all.iter().enumerate() {
+	    assert!(!algo.name().is_empty());
+	    assert!(all[..i].iter().all(|other| other.name() != algo.name()), "{}", algo.name());
+	    assert_eq!(generate(&ctx::Context::new_with_cost(algo.clone(), salt::Salt::default(), cost), "owo").unwrap().len(), kana_length(algo.digest_size()));
+	}
+	assert!(ctx::Algorithm::Sha256.is_cryptographic());
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_algorithms()
+    {
+	#[repr(C)]
+	struct Info
+	{
+	    id: u8,
+	    name: *const c_char,
+	    digest_size: libc::size_t,
+	    cryptographic: bool,
+	    extendable: bool,
+	}
+
+	let version = unsafe { std::ffi::CStr::from_ptr(khash_version()) };
+	assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+
+	let count = khash_algorithm_count();
+	assert_eq!(count, ctx::Algorithm::all().len());
+	for index in 0..count {
+	    let mut info = std::mem::MaybeUninit::<Info>::uninit();
+	    let info = unsafe {
+		assert_eq!(khash_algorithm_info(index, info.as_mut_ptr() as *mut c_void), GENERIC_SUCCESS);
+		info.assume_init()
+	    };
+	    let algo = &ctx::Algorithm::all()[index];
+	    assert!(khash_algorithm_supported(info.id));
+	    assert_eq!(&ctx::Algorithm::from(info.id), algo);
+	    assert_eq!(u8::try_from(algo.clone()).unwrap(), info.id);
+	    assert_eq!(unsafe { std::ffi::CStr::from_ptr(info.name) }.to_str().unwrap(), algo.name());
+	    assert_eq!(info.digest_size, algo.digest_size());
+	    assert_eq!((info.cryptographic, info.extendable), (algo.is_cryptographic(), algo.is_extendable()));
+
+	    assert_eq!(khash_algorithm_id(index), info.id);
+	    assert_eq!(unsafe { std::ffi::CStr::from_ptr(khash_algorithm_name(index)) }.to_str().unwrap(), algo.name());
+	    assert_eq!(khash_algorithm_digest_size(index), algo.digest_size());
+	    assert_eq!((khash_algorithm_cryptographic(index), khash_algorithm_extendable(index)), (algo.is_cryptographic(), algo.is_extendable()));
+	}
+	let mut info = std::mem::MaybeUninit::<Info>::uninit();
+	assert_eq!(unsafe { khash_algorithm_info(count, info.as_mut_ptr() as *mut c_void) }, 7);
+	assert_eq!(khash_algorithm_id(count), ctx::ALGO_DEFAULT);
+	assert!(khash_algorithm_name(count).is_null());
+	assert_eq!(khash_algorithm_digest_size(count), 0);
+	assert!(!khash_algorithm_cryptographic(count) && !khash_algorithm_extendable(count));
+	assert!(khash_algorithm_supported(0));
+	assert!(!khash_algorithm_supported(200));
+	assert_eq!(khash_algorithm_supported(1), cfg!(feature="crc"));
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_last_error()
+    {
+	fn message() -> String
+	{
+	    let mut buffer = [0u8; 256];
+	    let len = unsafe { khash_last_error_message(buffer.as_mut_ptr() as *mut c_char, buffer.len()) } as usize;
+	    let string = std::ffi::CStr::from_bytes_with_nul(&buffer[..=len]).unwrap().to_str().unwrap().to_owned();
+	    string
+	}
+	unsafe {
+	    let path = std::ffi::CString::new("/nonexistent/khash/path").unwrap();
+	    let raw = ctx::Context::default().into_raw().unwrap();
+	    let raw = &raw as *const ctx::CContext as *const c_void;
+	    assert_eq!(khash_do_path(raw, path.as_ptr(), std::ptr::null_mut(), 0, std::ptr::null_mut(), std::ptr::null_mut()), 1);
+	    assert!(message().starts_with("kana-hash error: io: "), "{}", message());
+
+	    let mut truncated = [0xffu8; 8];
+	    let len = khash_last_error_message(truncated.as_mut_ptr() as *mut c_char, truncated.len());
+	    assert_eq!(len as usize, message().len());
+	    assert_eq!(&truncated[..], b"kana-ha\0");
+
+	    assert_eq!(khash_free_context(raw as *mut c_void), GENERIC_SUCCESS);
+
+	    ffi::set_panic_error(std::panic::catch_unwind(|| panic!("owo")).unwrap_err());
+	    assert_eq!(message(), "kana-hash panic: owo");
+	    ffi::set_panic_error(std::panic::catch_unwind(|| panic!("uwu {}", 1)).unwrap_err());
+	    assert_eq!(message(), "kana-hash panic: uwu 1");
+
+	    assert_eq!(std::ffi::CStr::from_ptr(khash_strerror(8)).to_str().unwrap(), "buffer too small");
+	    assert_eq!(std::ffi::CStr::from_ptr(khash_strerror(1234)).to_str().unwrap(), "unrecognised error code");
+	}
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_hash()
+    {
+	let input = b"lolis are super ultra mega cute!";
+	let context = ctx::Context::new(ctx::Algorithm::Sha256, salt::Salt::default());
+	let expected = generate(&context, input).unwrap();
+	unsafe {
+	    let raw = context.into_raw().unwrap();
+	    let raw = &raw as *const ctx::CContext as *const c_void;
+	    for _ in 0..2 {
+		let mut output = std::ptr::null_mut();
+		let mut length = 0;
+		assert_eq!(khash_hash(raw, input.as_ptr() as *const c_void, input.len(), &mut output, &mut length), GENERIC_SUCCESS);
+		assert_eq!(length, expected.len());
+		assert_eq!(std::ffi::CStr::from_ptr(output).to_str().unwrap(), expected);
+		assert_eq!(khash_free_string(output), GENERIC_SUCCESS);
+	    }
+	    assert_eq!(khash_free_string(std::ptr::null_mut()), GENERIC_SUCCESS);
+	    assert_eq!(khash_free_context(raw as *mut c_void), GENERIC_SUCCESS);
+	}
+    }
+
+    #[test]
+    #[cfg(feature="ffi")]
+    fn ffi_sources()
+    {
+	extern "C" fn read_slice(userdata: *mut c_void, buffer: *mut c_void, size: libc::size_t) -> isize
+	{
+	    let input = unsafe { &mut *(userdata as *mut &[u8]) };
+	    let read = std::cmp::min(size, input.len());
+	    unsafe {
+		std::ptr::copy_nonoverlapping(input.as_ptr(), buffer as *mut u8, read);
+	    }
+	    *input = &input[read..];
+	    read as isize
+	}
+	extern "C" fn read_fail(_: *mut c_void, _: *mut c_void, _: libc::size_t) -> isize
+	{
+	    -1
+	}
+
+	let input = vec![0xabu8; 100_000];
+	let context = ctx::Context::new(ctx::Algorithm::Sha256, salt::Salt::default());
+	let expected = generate(&context, &input[..]).unwrap();
+	let path = std::env::temp_dir().join(format!("khash-ffi-sources-{}", std::process::id()));
+	std::fs::write(&path, &input[..]).unwrap();
+	let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+	unsafe {
+	    let raw = context.into_raw().unwrap();
+	    let raw = &raw as *const ctx::CContext as *const c_void;
+	    let mut output = vec![0u8; expected.len() + 1];
+	    let (mut read, mut length) = (0, 0);
+
+	    let mut source = &input[..];
+	    assert_eq!(khash_do_reader(raw, Some(read_slice), &mut source as *mut &[u8] as *mut c_void, output.as_mut_ptr() as *mut c_char, output.len(), &mut read, &mut length), GENERIC_SUCCESS);
+	    assert_eq!((read, length), (input.len(), expected.len()));
+	    assert_eq!(std::ffi::CStr::from_bytes_with_nul(&output[..]).unwrap().to_str().unwrap(), expected);
+	    assert_eq!(khash_do_reader(raw, Some(read_fail), std::ptr::null_mut(), output.as_mut_ptr() as *mut c_char, output.len(), &mut read, &mut length), 1);
+
+	    output.iter_mut().for_each(|b| *b = 0);
+	    assert_eq!(khash_do_path(raw, c_path.as_ptr(), output.as_mut_ptr() as *mut c_char, output.len(), &mut read, std::ptr::null_mut()), GENERIC_SUCCESS);
+	    assert_eq!(read, input.len());
+	    assert_eq!(std::ffi::CStr::from_bytes_with_nul(&output[..]).unwrap().to_str().unwrap(), expected);
+
+	    #[cfg(unix)]
+	    {
+		use std::os::unix::io::AsRawFd;
+		let file = std::fs::File::open(&path).unwrap();
+		output.iter_mut().for_each(|b| *b = 0);
+		assert_eq!(khash_do_fd(raw, file.as_raw_fd(), output.as_mut_ptr() as *mut c_char, output.len(), std::ptr::null_mut(), &mut length), GENERIC_SUCCESS);
+		assert_eq!(std::ffi::CStr::from_bytes_with_nul(&output[..]).unwrap().to_str().unwrap(), expected);
+	    }
+
+	    std::fs::remove_file(&path).unwrap();
+	    assert_eq!(khash_do_path(raw, c_path.as_ptr(), output.as_mut_ptr() as *mut c_char, output.len(), &mut read, &mut length), 1);
+	    assert_eq!(khash_free_context(raw as *mut c_void), GENERIC_SUCCESS);
+	}
+    }
+
     #[test]
     #[cfg(feature="ffi")]
     fn max_len()
@@ -188,7 +909,7 @@ mod tests {
 	{
 	    let mut output: libc::size_t = 0;
 	    unsafe {
-		assert_eq!(khash_max_length(algo.into(), data_len.into(), &mut output as *mut libc::size_t), GENERIC_SUCCESS);
+		assert_eq!(khash_max_length(u8::try_from(algo).unwrap(), data_len, &mut output as *mut libc::size_t), GENERIC_SUCCESS);
 	    }
 	    output
 	}
@@ -197,19 +918,143 @@ mod tests {
 
 	let algos = [#[cfg(feature="crc")] ctx::Algorithm::Crc32,
 		     #[cfg(feature="crc")] ctx::Algorithm::Crc64,
+		     #[cfg(feature="crc")] ctx::Algorithm::Crc32c,
+		     #[cfg(feature="crc")] ctx::Algorithm::Crc64Iso,
 		     ctx::Algorithm::Sha256,
-		     ctx::Algorithm::Sha256Truncated];
+		     ctx::Algorithm::Sha256Truncated,
+		     ctx::Algorithm::HmacSha256,
+		     ctx::Algorithm::HmacSha256Truncated,
+		     ctx::Algorithm::Blake3,
+		     ctx::Algorithm::Blake3Truncated,
+		     ctx::Algorithm::Sha512,
+		     ctx::Algorithm::Sha512_256,
+		     ctx::Algorithm::Sha3_256,
+		     #[cfg(feature="xxh3")] ctx::Algorithm::Xxh3_64,
+		     #[cfg(feature="xxh3")] ctx::Algorithm::Xxh3_128,
+		     #[cfg(feature="siphash")] ctx::Algorithm::SipHash13,
+		     ctx::Algorithm::Shake256];
 	for i in  0..1000
 	{
 	    let max_len = max_length(algos[i%algos.len()].clone(), 0);
 	    print!("{} - len of {:?}: {}... ", i, algos[i%algos.len()], max_len);
-	    let len = {
-		let con = ctx::Context::new(algos[i%algos.len()].clone(), salt::Salt::random().unwrap());
-		generate(&con, input).unwrap().len()
-	    };
-	    assert!(len < max_len);
+	    let con = ctx::Context::new(algos[i%algos.len()].clone(), salt::Salt::random().unwrap());
+	    let len = generate(&con, input).unwrap().len();
+	    assert!(len <= max_len);
 	    println!("\t\tOK {}", len);
 	}
+	// The longest output is with separators, and the longest digest length an FFI context can request
+	for algo in algos.iter() {
+	    let separators = ctx::OutputOptions{separators: true, ..Default::default()};
+	    let longest = ctx::Context::new(algo.clone(), salt::Salt::default()).with_output(separators);
+	    let longest = if algo.is_extendable() { longest.with_length(ctx::MAX_FFI_LENGTH) } else { longest };
+	    assert_eq!(generate(&longest, input).unwrap().len(), max_length(algo.clone(), 0));
+	}
+    }
+
+    /// Outputs of the native build, which every target (e.g. wasm32 under Node) must reproduce exactly.
+    #[cfg_attr(not(target_arch="wasm32"), test)]
+    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    fn known_vectors()
+    {
+	const INPUT: &[u8] = b"kana-hash test vector \x00\x01\xfe\xff";
+	// (algorithm ID, salt: 0 default, 1 specific, 2 none, kana)
+	const VECTORS: &[(u8, usize, &str)] = &[
+	    (1, 0, "をロそみ"),
+	    (1, 1, "らナアヌ"),
+	    (1, 2, "オつりっ"),
+	    (2, 0, "ウッツィネァヤぽ"),
+	    (2, 1, "すっソンヅョえリ"),
+	    (2, 2, "グヲもほヲむロネ"),
+	    (3, 0, "らぃいチセスみまブレらナうアレギヤわおソごぁほポレびヘゥビォみぉ"),
+	    (3, 1, "シァどウゲげさぃコあねシをあもナしやルあぶミねがゆめヂァベすセャ"),
+	    (3, 2, "なょのがそわばまやアつセヲもモッしサばょほぇイプやけコヤレユまシ"),
+	    (4, 0, "らぃいチセスみま"),
+	    (4, 1, "シァどウゲげさぃ"),
+	    (4, 2, "なょのがそわばま"),
+	    (5, 0, "ソふミメロィぬせまぃメそピォぢおがっウワきゃつラガムわリヅゥサゥ"),
+	    (5, 1, "れぱなぢシヤさぉおニノビこよセぴえあハサバァもぉトォミるユノボゥ"),
+	    (5, 2, "ンヨせねあヲさヲチあたぅりゃロけづグヅノソりそまンギヲれよまとが"),
+	    (6, 0, "ソふミメロィぬせ"),
+	    (6, 1, "れぱなぢシヤさぉ"),
+	    (6, 2, "ンヨせねあヲさヲ"),
+	    (7, 0, "らょイのノでんんヌごつレのぁニリレセらぇノうめゃべむオアわロバゥ"),
+	    (7, 1, "んすどシトェりぇピのガョヲやミゥパィげまヅつニヲアッばせういえめ"),
+	    (7, 2, "マよミワにっすぃぢえネなづくろぎインヌァやチセをウトるぐさゃびぉ"),
+	    (8, 0, "らょイのノでんん"),
+	    (8, 1, "んすどシトェりぇ"),
+	    (8, 2, "マよミワにっすぃ"),
+	    (9, 0, "くぁべっモィニッツェヤゆさゃサィユもがゅゆロムュにコびイちせげゆツォモッアうふシにまらゃマとゆえほられぅピビそゃリゥのわリォビア"),
+	    (9, 1, "ロてゲクうぽデェネンガャんツびテモョユのゲィちょトへにヲげゃネョれぅフゥむぉれづボクスいんなぺむピョせっブェゲパテュぱおメャしナ"),
+	    (9, 2, "ニのろロるばワまうさセソらルきブセョヌェなぉスこヨスユエニヨハべヌアぼまはゃもぃツッレチサすスチぼゃりゃゲくポるンニナのりゅすぇ"),
+	    (10, 0, "ヅワぢぇやギばぃがぉれせらネうラヲユノィダェをぼソあらぉレのノュ"),
+	    (10, 1, "テれルバムぽゴォネメロゥれみゆやすシツべぼデアつのゅりまぬっだわ"),
+	    (10, 2, "ヤオテふボゥナェばシげボでょスィナッニソスサまゃれユイロセチレャ"),
+	    (11, 0, "グネなテらゅムァゴォンぬわロるぉくぬイルナごバメヤそテとぶゅえト"),
+	    (11, 1, "さぱやっみせかぁエエたサヨばのゃうガヤプさぅねぅばものゅごょうユ"),
+	    (11, 2, "ヘひげウセこつケぐっつっおベめミあダいすピェあみうバトゥヤろケリ"),
+	    (12, 0, "エにヲビてぅモッ"),
+	    (12, 1, "げセガョベぼれっ"),
+	    (12, 2, "ケギろばユばめう"),
+	    (13, 0, "ぱゅはぅノれエスへイせイむょゲェ"),
+	    (13, 1, "グつウりモュうぬアろデャるゃギェ"),
+	    (13, 2, "ニケダァエカしイりぅネごぽゅれみ"),
+	    (14, 0, "さシタくグュミォ"),
+	    (14, 1, "いヲシヨなエぎぉ"),
+	    (14, 2, "ばエルギシッらょ"),
+	    (15, 0, "モォンシ"),
+	    (15, 1, "ろにスを"),
+	    (15, 2, "ビギせま"),
+	    (16, 0, "ベげヨヨぶらぱシ"),
+	    (16, 1, "ぬコえいロァアヌ"),
+	    (16, 2, "コいいエエクヨノ"),
+	    (17, 0, "らコれゅとぉルォウニめぉげぇベィオつミャけぃんすぴゅエアレゥネむ"),
+	    (17, 1, "ロけヲゲみぇみぅミチソネフキムおもゃんヲをダべえぬぉワぬハゥクィ"),
+	    (18, 0, "ぢケゴセらわぽっのぅつドしむレッそむぼぃムァそぃケャうヲポるゆひ"),
+	    (18, 1, "ノワテごアげレュべぇマをあユなゃげぁあクうナそリめぁりぉごぃノワ"),
+	    (18, 2, "だぉえるロィデぼきヲナェゲウでゃガへけゃろぅしぉひゅしっスァよワ"),
+	    (19, 0, "べっはょぼぇペゥのょサスもみあペスョすぉくぃろデまぃソゥマゥぬぇ"),
+	    (19, 1, "かぃのめづいナチニョヤわだぁねゅタェヨみるゆりがうセぢしんあごょ"),
+	    (19, 2, "なぃヤオビャねゃがツへゅきスシヲそろヌェすばナなレらヲべぢたメら"),
+	    (20, 0, "にぁめゅらオノィれやまイあぱオのまゅえっエノぬぇろくベるソュれみ"),
+	    (20, 1, "ンレダョかょウせスぼグッこペテノむほやヲクェさぅクんリュどぁナャ"),
+	    (20, 2, "ホらごぢワウぎねスクげリぺぇエんセォうすヂゥりょみぉミャミヨどリ"),
+	];
+	let salts = [salt::Salt::default(), salt::Salt::unfixed(b"kana-hash salt~"), salt::Salt::none()];
+	let cost = ctx::Cost{iterations: 1, memory_kib: 64, parallelism: 1};
+	for &(id, salt, expected) in VECTORS {
+	    if let Some(algo) = ctx::Algorithm::all().iter().find(|algo| u8::try_from((*algo).clone()).unwrap() == id) {
+		let context = ctx::Context::new_with_cost(algo.clone(), salts[salt].clone(), cost);
+		assert_eq!(generate(&context, INPUT).unwrap(), expected, "{:?} with salt {}", algo, salt);
+	    }
+	}
+
+	let outputs = [(ctx::Algorithm::Shake256, 48, ctx::Script::Katakana, "ンレ-ダョ-カョ-ウセ-スボ-グッ-コペ-テノ-ムホ-ヤヲ-クェ-サゥ-クン-リュ-ドァ-ナャ-エエ-ブェ-レノ-ナゲ-アッ-サフ-イメ-モゴ"),
+		       (ctx::Algorithm::Blake3, 5, ctx::Script::Hiragana, "んす-どし-どあ"),
+		       (ctx::Algorithm::Sha512, 3, ctx::Script::Mixed, "ロて-ケあ")];
+	for (algo, length, script, expected) in outputs.iter().cloned() {
+	    let context = ctx::Context::new(algo, salts[1].clone())
+		.with_length(length)
+		.with_output(ctx::OutputOptions{script, separators: true, ..Default::default()});
+	    assert_eq!(generate(&context, INPUT).unwrap(), expected);
+	}
+    }
+
+    #[cfg(all(feature="wasm", target_arch="wasm32"))]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn wasm_api()
+    {
+	const INPUT: &[u8] = b"kana-hash test vector \x00\x01\xfe\xff";
+	let mut context = wasm::Context::new(Some(ctx::ALGO_SHA256)).unwrap();
+	context.set_salt(&wasm::Salt::from_string("kana-hash salt~").unwrap());
+	let kana = context.hash(INPUT).unwrap();
+	assert_eq!(kana, "シァどウゲげさぃコあねシをあもナしやルあぶミねがゆめヂァベすセャ");
+	assert!(context.verify(INPUT, &kana).unwrap());
+	assert_eq!(context.kana_length(), kana.len());
+
+	assert_eq!(wasm::wasm_generate(b"hello").unwrap(), generate(&Default::default(), b"hello").unwrap());
+	assert_eq!(wasm::Salt::random().unwrap().bytes().len(), salt::SIZE);
+	assert!(wasm::Salt::new(b"").is_err());
+	assert!(wasm::Context::new(Some(200)).is_err());
     }
 }
 
@@ -227,31 +1072,56 @@ mod def;
 mod map;
 pub mod salt;
 mod hash;
-mod provider;
+pub mod provider;
 mod mnemonic;
 pub mod error;
 pub mod ctx;
 mod stream;
 pub use stream::Digest;
+mod hasher;
+pub use hasher::Hasher;
+pub mod codec;
 
 #[macro_use]
 mod ffi;
 use ffi::*;
 
-fn compute<T: Read>(context: &ctx::Context, mut from: T) -> Result<(usize, String), error::Error>
+/// The size in bytes of each kana in UTF-8.
+pub(crate) const KANA_UTF8_SIZE: usize = 3;
+
+/// The length in bytes of the kana output for a digest of `digest_size` bytes.
+///
+/// Each 2 bytes of digest (the last one padded if needed) become 2 kana.
+pub(crate) const fn kana_length(digest_size: usize) -> usize
 {
-    //let (read, hash) = provider::compute::<_, Digest>(&mut from, salt)?;
-    let (read, hash) = context.compute(&mut from)?;
+    digest_size.div_ceil(2) * 2 * KANA_UTF8_SIZE
+}
 
-    let mut output = String::with_capacity(128);
-    for element in hash.into_iter()
+fn kana(hash: &[u8], options: &ctx::OutputOptions) -> Result<String, error::Error>
+{
+    let mut output = String::with_capacity(options.kana_length(hash.len()));
+    let mut group = String::with_capacity(2 * KANA_UTF8_SIZE);
+    for (i, element) in hash.iter()
 	.into_16()
 	.map(|bytes| mnemonic::Digest::new(&u16::to_le_bytes(bytes)[..]))//unsafe{reinterpret::bytes(&bytes)}))
+	.enumerate()
     {
-	write!(output, "{}", element)?;
+	if i > 0 && options.separators {
+	    output.push(ctx::OutputOptions::SEPARATOR);
+	}
+	group.clear();
+	write!(group, "{}", element)?;
+	output.extend(group.chars().map(|kana| options.script.apply(kana)));
     }
+    Ok(output)
+}
+
+fn compute<I: ctx::Input>(context: &ctx::Context, from: I) -> Result<(usize, String), error::Error>
+{
+    //let (read, hash) = provider::compute::<_, Digest>(&mut from, salt)?;
+    let (read, hash) = context.compute(from)?;
 
-    Ok((read,output))
+    Ok((read, kana(&hash[..], context.get_output())?))
 }
 
 /// Generate kana hash from a slice of bytes with this digest.
@@ -265,8 +1135,7 @@ fn compute<T: Read>(context: &ctx::Context, mut from: T) -> Result<(usize, Strin
 pub fn generate<T: AsRef<[u8]>>(context: &ctx::Context, bytes: T) -> Result<String, error::Error>
 {
     let bytes = bytes.as_ref();
-    let mut nbytes = bytes;
-    let (ok, string) = compute(context, &mut nbytes)?;
+    let (ok, string) = compute(context, ctx::Slice(bytes))?;
     if ok == bytes.len() {
 	Ok(string)
     } else {
@@ -274,6 +1143,66 @@ pub fn generate<T: AsRef<[u8]>>(context: &ctx::Context, bytes: T) -> Result<Stri
     }
 }
 
+/// Generate kana hash from a slice of bytes with a custom digest provider.
+///
+/// # Example
+/// ```
+/// # use khash::{generate_with, provider::{ByteProvider, Incremental}, salt::Salt, error::Error};
+/// # use std::fmt;
+/// /// XOR of all the input and salt bytes
+/// struct Xor([u8; 1]);
+/// impl fmt::Display for Xor
+/// {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+///     {
+/// 	write!(f, "Xor ({:02x})", self.0[0])
+///     }
+/// }
+/// /// The XOR of the salt and the input so far
+/// struct XorState(u8);
+/// impl Incremental for XorState
+/// {
+///     type Output = Xor;
+///     fn new(salt: &Salt) -> Self
+///     {
+/// 	Self(salt.bytes().iter().fold(0, |a, b| a ^ b))
+///     }
+///     fn update(&mut self, bytes: &[u8])
+///     {
+/// 	self.0 = bytes.iter().fold(self.0, |a, b| a ^ b);
+///     }
+///     fn finish(self) -> Result<Xor, Error>
+///     {
+/// 	Ok(Xor([self.0]))
+///     }
+/// }
+/// impl ByteProvider for Xor
+/// {
+///     type State = XorState;
+///     fn bytes(&self) -> &[u8]
+///     {
+/// 	&self.0[..]
+///     }
+///     fn digest_size() -> usize
+///     {
+/// 	1
+///     }
+/// }
+///
+/// generate_with::<Xor, _>(&Salt::default(), "Hello world!").expect("Failed to generate hash string");
+/// ```
+pub fn generate_with<P: provider::ByteProvider, T: AsRef<[u8]>>(salt: &salt::Salt, bytes: T) -> Result<String, error::Error>
+{
+    let bytes = bytes.as_ref();
+    let mut ok = 0usize;
+    let hash = P::compute_slice(bytes, salt, &mut ok)?;
+    if ok == bytes.len() {
+	kana(hash.bytes(), &Default::default())
+    } else {
+	Err(error::Error::Length{expected: bytes.len(), got: ok})
+    }
+}
+
 /// Generate kana hash from a stream of bytes with this digest.
 /// # Example
 /// To generate a hash from a file with the default digest
@@ -299,6 +1228,37 @@ pub fn generate<T: AsRef<[u8]>>(context: &ctx::Context, bytes: T) -> Result<Stri
     compute(context, from)
 }
 
+/// Check a kana hash against a slice of bytes with this digest.
+///
+/// The newly computed kana hash is compared with `expected` in constant time, taking the same time whatever the length of `expected`.
+///
+/// # Note
+/// Many digests map to the same kana (and `OutputOptions` change how they are written), so `expected` cannot be decoded back into digest bytes.
+/// The kana strings are compared instead, which is what a hash produced by `generate` with `context` must match.
+///
+/// # Example
+/// ```
+/// # use khash::{generate, verify};
+/// let context = Default::default();
+/// let kana = generate(&context, "Hello world!").expect("Failed to generate hash string");
+/// assert!(verify(&context, "Hello world!", &kana).expect("Failed to verify hash string"));
+/// ```
+pub fn verify<T: AsRef<[u8]>>(context: &ctx::Context, bytes: T, expected: &str) -> Result<bool, error::Error>
+{
+    let string = generate(context, bytes)?;
+    Ok(array::ct_eq(string.as_bytes(), expected.as_bytes()))
+}
+
+/// Check a kana hash against a stream of bytes with this digest.
+///
+/// Returns the number of bytes read from the stream and whether the hash matched.
+/// The kana strings are compared in constant time, as in `verify`.
+pub fn verify_stream<T: Read+?Sized>(context: &ctx::Context, from: &mut T, expected: &str) -> Result<(usize, bool), error::Error>
+{
+    let (read, string) = compute(context, from)?;
+    Ok((read, array::ct_eq(string.as_bytes(), expected.as_bytes())))
+}
+
 use std::ffi::c_void;
 #[cfg(feature="ffi")] 
 use libc::{
@@ -318,3 +1278,13 @@ use malloc_array::{
 mod c;
 #[cfg(feature="ffi")] 
 pub use c::*;
+
+// WebAssembly section
+#[cfg(all(feature="ffi", target_arch="wasm32"))]
+compile_error!("the `ffi` feature needs libc and cannot be built for wasm32, build with `--no-default-features --features wasm` instead");
+#[cfg(feature="wasm")]
+pub mod wasm;
+
+// JNI section
+#[cfg(feature="jni")]
+mod jni;
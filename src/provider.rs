@@ -1,11 +1,242 @@
+//! Digest providers.
+//!
+//! Implement `ByteProvider` for an in-house digest to use it with `generate_with`, or `DynProvider` to carry it in a `Context` with `ctx::Algorithm::Custom`.
+
 use crate::*;
+use std::{
+    fmt,
+    io,
+    marker::PhantomData,
+};
+
+/// The running state of a digest, fed its input in pieces.
+///
+/// `ByteProvider::compute` is built on this, and `Hasher` uses it to hash incrementally.
+pub trait Incremental: Sized
+{
+    /// The digest this produces
+    type Output;
+    /// Start a digest with this salt
+    fn new(salt: &salt::Salt) -> Self;
+    /// Feed more input into the digest
+    fn update(&mut self, bytes: &[u8]);
+    /// Finish the digest
+    fn finish(self) -> Result<Self::Output, error::Error>;
+}
+
+/// Feed all of a stream to `update`, returning the number of bytes read.
+pub(crate) fn update_from<T: Read + ?Sized, F: FnMut(&[u8])>(input: &mut T, mut update: F) -> io::Result<usize>
+{
+    let mut buffer = [0u8; BUFFER_SIZE];
+    let mut read;
+    let mut done=0;
+    while (read = input.read(&mut buffer[..])?, read!=0).1
+    {
+	update(&buffer[..read]);
+	done+=read;
+    }
+    Ok(done)
+}
 
+/// A digest algorithm that produces bytes to be turned into kana.
 pub trait ByteProvider: Sized + std::fmt::Display
 {
-    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, provided: &mut usize) -> Result<Self, error::Error>;
+    /// The running state of this digest
+    type State: Incremental<Output = Self>;
+    /// Compute the digest of a stream, setting `provided` to the number of bytes read from it.
+    fn compute<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, provided: &mut usize) -> Result<Self, error::Error>
+    {
+	let mut state = Self::State::new(salt);
+	*provided = update_from(input, |bytes| state.update(bytes))?;
+	state.finish()
+    }
+    /// Compute from a whole slice.
+    fn compute_slice(input: &[u8], salt: &salt::Salt, provided: &mut usize) -> Result<Self, error::Error>
+    {
+	let mut state = Self::State::new(salt);
+	state.update(input);
+	*provided = input.len();
+	state.finish()
+    }
+    /// The bytes of the digest
     fn bytes(&self) -> &[u8];
+    /// The size in bytes of the digests this provider produces.
+    fn digest_size() -> usize;
+}
+
+
+/// A provider with an extendable output, which can produce any length of digest.
+pub trait XofProvider: ByteProvider
+{
+    /// Finish a running digest with `length` bytes of output.
+    fn finish_xof(state: Self::State, length: usize) -> Result<Box<[u8]>, error::Error>;
+    /// Compute a digest of `length` bytes from a stream, setting `provided` to the number of bytes read from it.
+    fn compute_xof<T: Read + ?Sized>(input: &mut T, salt: &salt::Salt, length: usize, provided: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	let mut state = Self::State::new(salt);
+	*provided = update_from(input, |bytes| state.update(bytes))?;
+	Self::finish_xof(state, length)
+    }
+    /// Compute from a whole slice. (See `ByteProvider::compute_slice`.)
+    fn compute_xof_slice(input: &[u8], salt: &salt::Salt, length: usize, provided: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	let mut state = Self::State::new(salt);
+	state.update(input);
+	*provided = input.len();
+	Self::finish_xof(state, length)
+    }
+}
+
+/// The running state of a digest, as an object. Created by `DynProvider::begin`.
+pub trait DynState: Send
+{
+    /// Feed more input into the digest
+    fn update(&mut self, bytes: &[u8]);
+    /// Finish the digest and return its bytes
+    fn finish(self: Box<Self>) -> Result<Box<[u8]>, error::Error>;
+}
+
+/// The running state of the `ByteProvider` `P`, as a `DynState`.
+pub(crate) struct Running<P: ByteProvider>(P::State);
+
+impl<P: ByteProvider> Running<P>
+{
+    pub fn new(salt: &salt::Salt) -> Self
+    {
+	Self(P::State::new(salt))
+    }
+}
+
+impl<P: ByteProvider> DynState for Running<P>
+where P::State: Send
+{
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.0.update(bytes)
+    }
+    fn finish(self: Box<Self>) -> Result<Box<[u8]>, error::Error>
+    {
+	Ok(Box::from(self.0.finish()?.bytes()))
+    }
+}
+
+/// The running state of the `XofProvider` `P` with a `length` byte output, as a `DynState`.
+pub(crate) struct RunningXof<P: XofProvider>
+{
+    state: P::State,
+    length: usize,
 }
 
+impl<P: XofProvider> RunningXof<P>
+{
+    pub fn new(salt: &salt::Salt, length: usize) -> Self
+    {
+	Self {
+	    state: P::State::new(salt),
+	    length,
+	}
+    }
+}
+
+impl<P: XofProvider> DynState for RunningXof<P>
+where P::State: Send
+{
+    fn update(&mut self, bytes: &[u8])
+    {
+	self.state.update(bytes)
+    }
+    fn finish(self: Box<Self>) -> Result<Box<[u8]>, error::Error>
+    {
+	P::finish_xof(self.state, self.length)
+    }
+}
+
+/// An object-safe digest provider, to be carried in a `Context` as `ctx::Algorithm::Custom`.
+///
+/// Any `ByteProvider` can be used as one with `of`.
+///
+/// # Equality
+/// Two `Custom` algorithms are equal (and hash the same) only if they share the same provider object.
+pub trait DynProvider: Send + Sync + fmt::Debug
+{
+    /// Start a digest with this salt
+    fn begin(&self, salt: &salt::Salt) -> Box<dyn DynState>;
+    /// Compute the digest of a stream, setting `provided` to the number of bytes read from it.
+    fn compute_dyn(&self, input: &mut dyn Read, salt: &salt::Salt, provided: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	let mut state = self.begin(salt);
+	*provided = update_from(input, |bytes| state.update(bytes))?;
+	state.finish()
+    }
+    /// Compute from a whole slice. (See `ByteProvider::compute_slice`.)
+    fn compute_dyn_slice(&self, input: &[u8], salt: &salt::Salt, provided: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	let mut state = self.begin(salt);
+	state.update(input);
+	*provided = input.len();
+	state.finish()
+    }
+    /// The size in bytes of the digests this provider produces.
+    fn digest_size(&self) -> usize;
+}
+
+impl PartialEq for dyn DynProvider
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+	std::ptr::eq(self as *const Self as *const u8, other as *const Self as *const u8)
+    }
+}
+impl Eq for dyn DynProvider{}
+
+impl std::hash::Hash for dyn DynProvider
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H)
+    {
+	(self as *const Self as *const u8).hash(state)
+    }
+}
+
+/// A `DynProvider` for a `ByteProvider` type. Created with `of`.
+pub struct Generic<P>(PhantomData<fn() -> P>);
+
+impl<P> fmt::Debug for Generic<P>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	write!(f, "Generic<{}>", std::any::type_name::<P>())
+    }
+}
+
+impl<P: ByteProvider + 'static> DynProvider for Generic<P>
+where P::State: Send
+{
+    fn begin(&self, salt: &salt::Salt) -> Box<dyn DynState>
+    {
+	Box::new(Running::<P>::new(salt))
+    }
+    fn compute_dyn(&self, input: &mut dyn Read, salt: &salt::Salt, provided: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	Ok(Box::from(P::compute(input, salt, provided)?.bytes()))
+    }
+    fn compute_dyn_slice(&self, input: &[u8], salt: &salt::Salt, provided: &mut usize) -> Result<Box<[u8]>, error::Error>
+    {
+	Ok(Box::from(P::compute_slice(input, salt, provided)?.bytes()))
+    }
+    fn digest_size(&self) -> usize
+    {
+	P::digest_size()
+    }
+}
+
+/// Create a `DynProvider` for the `ByteProvider` `P`, to be used with `ctx::Algorithm::Custom`.
+///
+/// The digest size reported is `ByteProvider::digest_size` of `P`.
+pub fn of<P: ByteProvider + 'static>() -> std::sync::Arc<dyn DynProvider>
+where P::State: Send
+{
+    std::sync::Arc::new(Generic::<P>(PhantomData))
+}
 
 pub fn compute<T: Read + ?Sized, P: ByteProvider>(input: &mut T, salt: salt::Salt) -> Result<(usize, P), error::Error>
 {
@@ -137,28 +137,56 @@ pub(crate) const SALT_TYPE_SPECIFIC: u8 = 2;
 pub(crate) const SALT_TYPE_RANDOM: u8 = 3;
 
 /// We won't try to copy more than this much data.
-const MAX_FFI_SALT_SIZE: usize = 1024;
+pub(crate) const MAX_FFI_SALT_SIZE: usize = 1024;
+
+/// Check the body of a specific salt passed through FFI before reading it.
+#[cfg(feature="ffi")] pub(crate) fn check_specific(body: *const u8, size: usize) -> Result<(), crate::error::Error>
+{
+    if size == 0 {
+	Err(crate::error::Error::EmptySalt)
+    } else if size > MAX_FFI_SALT_SIZE {
+	Err(crate::error::Error::SaltTooLarge{size, max: MAX_FFI_SALT_SIZE})
+    } else if body.is_null() {
+	Err(crate::error::Error::NullPointer("salt"))
+    } else {
+	Ok(())
+    }
+}
+
+/// Check an `FFI` salt before reading it.
+#[cfg(feature="ffi")] fn check(ffi: &FFI) -> Result<(), crate::error::Error>
+{
+    match ffi.salt_type {
+	SALT_TYPE_SPECIFIC => check_specific(ffi.body, usize::try_from(ffi.size).unwrap()),
+	SALT_TYPE_NONE | SALT_TYPE_DEFAULT | SALT_TYPE_RANDOM => Ok(()),
+	salt_type => Err(crate::error::Error::UnknownSaltType(salt_type)),
+    }
+}
 
 /// Clone a new `Salt` from an `FFI` salt.
-#[cfg(feature="ffi")] pub(crate) unsafe fn clone_from_raw(ptr: *const FFI) -> Salt
+#[cfg(feature="ffi")] pub(crate) unsafe fn clone_from_raw(ptr: *const FFI) -> Result<Salt, crate::error::Error>
 {
     let ffi = &*ptr;
-    match ffi.salt_type {
-	SALT_TYPE_SPECIFIC if ffi.size > 0 => {
+    check(ffi)?;
+    Ok(match ffi.salt_type {
+	SALT_TYPE_SPECIFIC => {
 	    Salt::Dynamic(HeapArray::from_raw_copied(ffi.body as *const u8, usize::try_from(ffi.size).unwrap()).into_boxed_slice())
 	},
 	SALT_TYPE_DEFAULT => {
 	    Salt::default()
 	},
 	_ => Salt::None,
-    }
+    })
 }
 /// Consume an `FFI` salt and return a `Salt`.
-#[cfg(feature="ffi")]  pub(crate) unsafe fn from_raw(ptr: *mut FFI) -> Salt
+///
+/// An invalid salt is not consumed.
+#[cfg(feature="ffi")]  pub(crate) unsafe fn from_raw(ptr: *mut FFI) -> Result<Salt, crate::error::Error>
 {
     let ffi = &mut *ptr;
+    check(ffi)?;
     let out = match ffi.salt_type {
-	SALT_TYPE_SPECIFIC if ffi.size > 0 => {
+	SALT_TYPE_SPECIFIC => {
 	    Salt::Dynamic(HeapArray::from_raw_parts(ffi.body as *mut u8, usize::try_from(ffi.size).unwrap()).into_boxed_slice())
 	},
 	SALT_TYPE_DEFAULT => {
@@ -169,7 +197,7 @@ const MAX_FFI_SALT_SIZE: usize = 1024;
     ffi.salt_type = SALT_TYPE_NONE;
     ffi.size = 0;
     ffi.body = 0 as *mut u8;
-    out
+    Ok(out)
 }
 
 /// Consume a `Salt` and output a new `FFI` salt.
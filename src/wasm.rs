@@ -0,0 +1,226 @@
+//! WebAssembly bindings (requires the `wasm` feature.)
+//!
+//! Build for `wasm32-unknown-unknown` without the default `ffi` and `threads` features, e.g. with `wasm-pack build -- --no-default-features --features wasm,crc,xxh3,siphash,kdf`.
+//! `Salt.random()` uses the JS environment's `crypto.getRandomValues` (or Node's `crypto` module.)
+use super::*;
+use wasm_bindgen::prelude::*;
+
+fn to_js(error: error::Error) -> JsError
+{
+    JsError::new(&error.to_string())
+}
+
+/// The kana script a hash is written in
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script
+{
+    Mixed = 0,
+    Hiragana = 1,
+    Katakana = 2,
+}
+
+impl From<Script> for ctx::Script
+{
+    fn from(from: Script) -> Self
+    {
+	match from {
+	    Script::Mixed => Self::Mixed,
+	    Script::Hiragana => Self::Hiragana,
+	    Script::Katakana => Self::Katakana,
+	}
+    }
+}
+
+/// A salt for the hash. `new Salt(bytes)` uses `bytes` (which cannot be empty) as the salt.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Salt
+{
+    inner: salt::Salt,
+}
+
+#[wasm_bindgen]
+impl Salt
+{
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<Salt, JsError>
+    {
+	if bytes.is_empty() {
+	    return Err(to_js(error::Error::EmptySalt));
+	}
+	Ok(Self {
+	    inner: salt::Salt::unfixed(bytes),
+	})
+    }
+
+    /// A salt from the UTF-8 bytes of `string`
+    #[wasm_bindgen(js_name = fromString)]
+    pub fn from_string(string: &str) -> Result<Salt, JsError>
+    {
+	Self::new(string.as_bytes())
+    }
+
+    /// No salt
+    pub fn none() -> Salt
+    {
+	Self {
+	    inner: salt::Salt::none(),
+	}
+    }
+
+    /// The library's default embedded salt
+    pub fn internal() -> Salt
+    {
+	Self {
+	    inner: salt::Salt::internal(),
+	}
+    }
+
+    /// A cryptographically secure random salt
+    pub fn random() -> Result<Salt, JsError>
+    {
+	Ok(Self {
+	    inner: salt::Salt::random().map_err(|e| to_js(e.into()))?,
+	})
+    }
+
+    /// The bytes of the salt
+    #[wasm_bindgen(getter)]
+    pub fn bytes(&self) -> Vec<u8>
+    {
+	self.inner.bytes().to_vec()
+    }
+}
+
+/// A kana-hash context. `new Context()` uses the default algorithm and salt.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Context
+{
+    inner: ctx::Context,
+}
+
+#[wasm_bindgen]
+impl Context
+{
+    /// Create a context with `algorithm` (one of the `KHASH_ALGO_*` values, `0` or `undefined` for the default) and the default salt.
+    #[wasm_bindgen(constructor)]
+    pub fn new(algorithm: Option<u8>) -> Result<Context, JsError>
+    {
+	let algo = ctx::Algorithm::from_ffi(algorithm.unwrap_or(ctx::ALGO_DEFAULT)).map_err(to_js)?;
+	Ok(Self {
+	    inner: ctx::Context::new(algo, salt::Salt::default()),
+	})
+    }
+
+    /// Replace the algorithm, one of the `KHASH_ALGO_*` values
+    #[wasm_bindgen(js_name = setAlgorithm)]
+    pub fn set_algorithm(&mut self, algorithm: u8) -> Result<(), JsError>
+    {
+	self.inner.set_algorithm(ctx::Algorithm::from_ffi(algorithm).map_err(to_js)?);
+	Ok(())
+    }
+
+    /// Replace the salt. `salt` is copied and can still be used afterwards.
+    #[wasm_bindgen(js_name = setSalt)]
+    pub fn set_salt(&mut self, salt: &Salt)
+    {
+	self.inner.set_salt(salt.inner.clone());
+    }
+
+    /// Set the cost parameters of the password-hashing algorithms, `0` uses the algorithm's default.
+    #[wasm_bindgen(js_name = setCost)]
+    pub fn set_cost(&mut self, iterations: u32, memory_kib: u32, parallelism: u32)
+    {
+	self.inner.set_cost(ctx::Cost {
+	    iterations,
+	    memory_kib,
+	    parallelism,
+	});
+    }
+
+    /// Request a digest length in bytes, `0` for the algorithm's own.
+    #[wasm_bindgen(js_name = setLength)]
+    pub fn set_length(&mut self, length: usize)
+    {
+	self.inner.set_length(match length {
+	    0 => None,
+	    length => Some(length),
+	});
+    }
+
+    /// Set the script the kana are written in, and whether a `-` is written between each group of 2 kana.
+    #[wasm_bindgen(js_name = setOutput)]
+    pub fn set_output(&mut self, script: Script, separators: bool)
+    {
+	self.inner.set_output(ctx::OutputOptions {
+	    script: script.into(),
+	    separators,
+	    ..Default::default()
+	});
+    }
+
+    /// Compute the kana hash of `bytes`
+    pub fn hash(&self, bytes: &[u8]) -> Result<String, JsError>
+    {
+	generate(&self.inner, bytes).map_err(to_js)
+    }
+
+    /// Compute the kana hash of the UTF-8 bytes of `string`
+    #[wasm_bindgen(js_name = hashString)]
+    pub fn hash_string(&self, string: &str) -> Result<String, JsError>
+    {
+	self.hash(string.as_bytes())
+    }
+
+    /// Check the kana hash of `bytes` against `expected` in constant time
+    pub fn verify(&self, bytes: &[u8], expected: &str) -> Result<bool, JsError>
+    {
+	verify(&self.inner, bytes, expected).map_err(to_js)
+    }
+
+    /// The `KHASH_ALGO_*` value of the algorithm
+    #[wasm_bindgen(getter)]
+    pub fn algorithm(&self) -> Result<u8, JsError>
+    {
+	u8::try_from(self.inner.get_algorithm().clone()).map_err(to_js)
+    }
+
+    /// A copy of the salt
+    #[wasm_bindgen(getter)]
+    pub fn salt(&self) -> Salt
+    {
+	Salt {
+	    inner: self.inner.get_salt().clone(),
+	}
+    }
+
+    /// The size in bytes of the digest
+    #[wasm_bindgen(getter, js_name = digestSize)]
+    pub fn digest_size(&self) -> usize
+    {
+	self.inner.digest_size()
+    }
+
+    /// The length in bytes (as UTF-8) of the kana hashes
+    #[wasm_bindgen(getter, js_name = kanaLength)]
+    pub fn kana_length(&self) -> usize
+    {
+	self.inner.kana_length()
+    }
+}
+
+/// Compute the kana hash of `bytes` with the default context
+#[wasm_bindgen(js_name = generate)]
+pub fn wasm_generate(bytes: &[u8]) -> Result<String, JsError>
+{
+    generate(&Default::default(), bytes).map_err(to_js)
+}
+
+/// The version of the library
+#[wasm_bindgen]
+pub fn version() -> String
+{
+    env!("CARGO_PKG_VERSION").to_owned()
+}